@@ -1,5 +1,7 @@
 use anyhow::{anyhow, Result};
 use crate::memory::MemoryStore;
+#[cfg(feature = "web")]
+use chrono::{DateTime, Utc};
 use crate::settings::Settings;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -14,11 +16,18 @@ pub struct AgentProfile {
 	pub persona: String,
 }
 
+#[cfg(feature = "web")]
+const CHUNK_CHARS: usize = 6000;
+#[cfg(feature = "web")]
+const SEARCH_EXCERPT_CHARS: usize = 3000;
+
 pub struct Agent {
 	settings: Settings,
 	memory: Arc<MemoryStore>,
 	profile: AgentProfile,
 	llm: LlmClient,
+	/// Set by a voice command; otherwise the persona's default language
+	reply_language: Option<String>,
 }
 
 impl Agent {
@@ -30,19 +39,119 @@ impl Agent {
 			persona: settings.persona.clone().unwrap_or_else(|| "Ramah, ingin tahu, membantu".to_string()),
 		};
 		let llm = LlmClient::new(settings.clone());
-		Ok(Self { settings, memory, profile, llm })
+		Ok(Self { settings, memory, profile, llm, reply_language: None })
+	}
+
+	#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+	pub fn set_reply_language(&mut self, language: Option<String>) {
+		self.reply_language = language;
 	}
 
-	pub async fn respond(&mut self, user_input: &str) -> Result<String> {
+	/// Answer the user, handing reply text to `on_text` as it is generated so speech can start early.
+	pub async fn respond_streaming(&mut self, user_input: &str, on_text: &mut dyn FnMut(&str)) -> Result<String> {
+		#[cfg(feature = "web")]
+		if self.settings.search_url.is_some() {
+			if let Some(query) = crate::modules::search::search_query(user_input) {
+				match self.answer_with_search(user_input, query).await {
+					Ok(reply) => {
+						on_text(&reply);
+						return Ok(reply);
+					}
+					Err(e) => tracing::warn!("web search failed, answering from memory: {}", e),
+				}
+			}
+		}
 		let context = self.memory.recall_recent(8)?;
 		let prompt = self.build_prompt(user_input, &context)?;
-		let reply = self.llm.generate(&prompt).await.unwrap_or_else(|_| "[offline] LLM unavailable".to_string());
+		let reply = match self.llm.generate_stream(&prompt, on_text).await {
+			Ok(reply) => reply,
+			Err(_) => {
+				let reply = "[offline] LLM unavailable".to_string();
+				on_text(&reply);
+				reply
+			}
+		};
+		self.memory.append_interaction(user_input, &reply)?;
+		Ok(reply)
+	}
+
+	/// Describe a picture (optionally answering `question` about it) and remember what was seen.
+	#[cfg(feature = "vision")]
+	pub async fn look(&mut self, picture: &crate::modules::vision::Picture, question: Option<&str>) -> Result<String> {
+		let mut prompt = "Jelaskan secara ringkas dan faktual apa yang terlihat pada gambar ini: objek, orang, teks yang terbaca, dan suasananya.".to_string();
+		if let Some(q) = question { prompt.push_str(&format!(" Lalu jawab pertanyaan pengguna: {}", q)); }
+		match &self.reply_language {
+			Some(lang) => prompt.push_str(&format!(" Jawab dalam {}.", lang)),
+			None => prompt.push_str(" Jawab dalam bahasa Indonesia."),
+		}
+		let description = self.llm.describe_image(&prompt, &picture.jpeg).await?;
+		let note_user = match question {
+			Some(q) => format!("LOOK AT: {} ({})", picture.source, q),
+			None => format!("LOOK AT: {}", picture.source),
+		};
+		self.memory.append_interaction(&note_user, description.trim())?;
+		Ok(description.trim().to_string())
+	}
+
+	/// Search the web, read the top results under the domain policy, and answer citing them as [n].
+	#[cfg(feature = "web")]
+	pub async fn answer_with_search(&mut self, user_input: &str, query: &str) -> Result<String> {
+		use crate::modules::{search, web};
+		let max = self.settings.search_max_results.unwrap_or(3);
+		let results = search::search(&self.settings, query).await?;
+		let mut sources: Vec<(String, String)> = Vec::new();
+		let mut body = String::new();
+		for r in results {
+			if sources.len() >= max { break; }
+			// results outside allow_domains / robots policy are skipped, not just their text
+			let text = match web::fetch_text(&self.settings, &r.url).await {
+				Ok(t) if !t.trim().is_empty() => t,
+				_ => continue,
+			};
+			let excerpt: String = text.chars().take(SEARCH_EXCERPT_CHARS).collect();
+			sources.push((r.title.clone(), r.url.clone()));
+			body.push_str(&format!("[{n}] {title} ({url})\n{excerpt}\n\n", n = sources.len(), title = r.title, url = r.url, excerpt = excerpt.trim()));
+		}
+		if sources.is_empty() { return Err(anyhow!("no usable search results for: {}", query)); }
+		let context = self.memory.recall_recent(4)?;
+		let prompt = format!(
+			"<SYSTEM>\nAnda adalah {name}. Jawab pertanyaan pengguna hanya berdasarkan SUMBER di bawah, ringkas, dalam bahasa pengguna. Tandai setiap fakta dengan rujukan seperti [1]. Jika sumber tidak cukup, katakan demikian.\n</SYSTEM>\n<CONTEXT>\n{ctx}\n</CONTEXT>\n<SOURCES>\n{body}</SOURCES>\n<USER>\n{user}\n</USER>\n",
+			name = self.profile.name,
+			ctx = context,
+			body = body,
+			user = user_input,
+		);
+		let answer = self.llm.generate(&prompt).await.unwrap_or_else(|_| "[offline] LLM unavailable".to_string());
+		let mut reply = answer.trim().to_string();
+		reply.push_str("\n\nSumber:");
+		for (i, (title, url)) in sources.iter().enumerate() {
+			reply.push_str(&format!("\n[{}] {} - {}", i + 1, title, url));
+		}
 		self.memory.append_interaction(user_input, &reply)?;
 		Ok(reply)
 	}
 
+	/// Summarize `text` into memory, one note per chunk so long documents fit the model context.
 	#[cfg(feature = "web")]
 	pub async fn summarize_and_learn(&mut self, source: &str, text: &str) -> Result<String> {
+		self.summarize_and_learn_dated(source, None, text).await
+	}
+
+	/// Like `summarize_and_learn`, recording the source's publication date with each note.
+	#[cfg(feature = "web")]
+	pub async fn summarize_and_learn_dated(&mut self, source: &str, published: Option<DateTime<Utc>>, text: &str) -> Result<String> {
+		let chunks = chunk_text(text, CHUNK_CHARS);
+		let total = chunks.len();
+		let mut summaries = Vec::with_capacity(total);
+		for (i, chunk) in chunks.into_iter().enumerate() {
+			let part = if total > 1 { format!("{src} (bagian {n}/{total})", src = source, n = i + 1) } else { source.to_string() };
+			summaries.push(self.summarize_chunk(&part, published, chunk).await?);
+		}
+		Ok(summaries.join("\n"))
+	}
+
+	#[cfg(feature = "web")]
+	async fn summarize_chunk(&mut self, source: &str, published: Option<DateTime<Utc>>, text: &str) -> Result<String> {
 		let instruction = format!(
 			"Ringkas konten berikut dalam 5-8 poin (bahasa Indonesia), fokuskan pada fakta inti dan insight. Sumber: {src}\n\n{body}",
 			src = source,
@@ -51,7 +160,10 @@ impl Agent {
 		let context = self.memory.recall_recent(4)?;
 		let prompt = format!("<SYSTEM>Anda adalah {name} yang ingin tahu dan sedang belajar dari web.</SYSTEM>\n<CONTEXT>\n{ctx}\n</CONTEXT>\n<USER>\n{inst}\n</USER>", name = self.profile.name, ctx = context, inst = instruction);
 		let summary = self.llm.generate(&prompt).await.unwrap_or_else(|_| "[offline] summary unavailable".to_string());
-		let note_user = format!("LEARN FROM: {src}", src = source);
+		let note_user = match published {
+			Some(date) => format!("LEARN FROM: {src} (published {date})", src = source, date = date.to_rfc3339()),
+			None => format!("LEARN FROM: {src}", src = source),
+		};
 		self.memory.append_interaction(&note_user, &summary)?;
 		Ok(summary)
 	}
@@ -60,13 +172,16 @@ impl Agent {
 		let mut seed: u64 = rand::thread_rng().gen();
 		let style = self.settings.speaking_style.clone().unwrap_or_else(|| "hangat, natural, sopan".to_string());
 		let max_sent = self.settings.response_max_sentences.unwrap_or(3);
-		let system = format!(
+		let mut system = format!(
 			"Anda adalah {name}, asisten AI berbahasa Indonesia yang ingin tahu dan berkembang. Persona: {persona}. Gaya bicara: {style}.\nJawab ringkas (maks {max_sent} kalimat) kecuali diminta detail.\n",
 			name = self.profile.name,
 			persona = self.profile.persona,
 			style = style,
 			max_sent = max_sent,
 		);
+		if let Some(lang) = &self.reply_language {
+			system.push_str(&format!("Selalu jawab dalam {}.\n", lang));
+		}
 		let prompt = format!(
 			"<SYSTEM>\n{system}\n<CONTEXT>\n{context}\n</CONTEXT>\n<USER>\n{user}\n</USER>\n",
 			system = system,
@@ -76,4 +191,46 @@ impl Agent {
 		let _ = seed; // reserved for stochastic settings later
 		Ok(prompt)
 	}
-} 
\ No newline at end of file
+}
+
+/// Split text into chunks of at most `max_chars`, preferring paragraph, then line, then sentence boundaries.
+#[cfg(feature = "web")]
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<&str> {
+	let mut chunks = Vec::new();
+	let mut rest = text.trim();
+	while rest.chars().count() > max_chars {
+		let limit = rest.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(rest.len());
+		let window = &rest[..limit];
+		let cut = window.rfind("\n\n").or_else(|| window.rfind('\n')).or_else(|| window.rfind(". ").map(|i| i + 1)).filter(|&i| i > 0).unwrap_or(limit);
+		chunks.push(rest[..cut].trim());
+		rest = rest[cut..].trim_start();
+	}
+	if !rest.is_empty() || chunks.is_empty() { chunks.push(rest); }
+	chunks
+}
+
+#[cfg(all(test, feature = "web"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn chunk_text_prefers_paragraphs_then_lines_then_sentences() {
+		assert_eq!(chunk_text("aaaa\nbb\n\ncccc\ndddd", 14), ["aaaa\nbb", "cccc\ndddd"]);
+		assert_eq!(chunk_text("aaaa\nbbbb\ncccc", 12), ["aaaa\nbbbb", "cccc"]);
+		assert_eq!(chunk_text("One two. Three four. Five", 15), ["One two.", "Three four.", "Five"]);
+		// no boundary at all: a hard cut at the limit
+		assert_eq!(chunk_text("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+		assert_eq!(chunk_text("  short  ", 100), ["short"]);
+		assert_eq!(chunk_text("", 10), [""]);
+	}
+
+	#[test]
+	fn chunk_text_counts_characters_not_bytes() {
+		let chunks = chunk_text("ééééé ééééé", 4);
+		assert_eq!(chunks, ["éééé", "é éé", "ééé"]);
+		let text = "Apa kabar? Baik. ".repeat(20) + "😀".repeat(30).as_str();
+		for chunk in chunk_text(&text, 25) {
+			assert!(chunk.chars().count() <= 25, "{}", chunk);
+		}
+	}
+}
\ No newline at end of file
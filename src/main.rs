@@ -22,30 +22,195 @@ struct Cli {
 enum Commands {
 	/// Run interactive assistant (text REPL)
 	Run,
-	/// Say a message via TTS (requires --features tts)
-	#[cfg(feature = "tts")]
-	Say { text: String },
+	/// Say a message via TTS (requires --features tts or tts-process)
+	#[cfg(feature = "speech")]
+	Say {
+		text: String,
+		/// Render to this WAV file instead of playing (piper/espeak backends)
+		#[arg(long)]
+		out: Option<String>,
+	},
 	/// Listen on mic and transcribe one utterance (requires --features stt-vosk)
 	#[cfg(feature = "stt-vosk")]
 	Listen,
+	/// Transcribe a WAV file, or raw s16le PCM from stdin with "-" (requires --features stt-vosk)
+	#[cfg(feature = "stt-vosk")]
+	Transcribe {
+		file: String,
+		/// Sample rate of raw stdin PCM
+		#[arg(long, default_value_t = 16000)]
+		rate: u32,
+		/// Channel count of raw stdin PCM
+		#[arg(long, default_value_t = 1)]
+		channels: u16,
+	},
+	/// Audio device utilities (requires --features stt-vosk)
+	#[cfg(feature = "stt-vosk")]
+	Audio {
+		#[command(subcommand)]
+		action: AudioAction,
+	},
 	/// Capture one camera frame to file (requires --features camera)
 	#[cfg(feature = "camera")]
 	Snapshot { output: String },
-	/// Voice mode with wake word (requires --features tts,stt-vosk)
-	#[cfg(all(feature = "stt-vosk", feature = "tts"))]
-	Voice,
-	/// Continuous voice chat with silence detection (requires --features tts,stt-vosk)
-	#[cfg(all(feature = "stt-vosk", feature = "tts"))]
+	/// Describe a camera frame, or an image file, with a multimodal model (requires --features vision)
+	#[cfg(feature = "vision")]
+	Look {
+		/// Image file to describe instead of a camera frame
+		#[arg(long)]
+		image: Option<String>,
+		/// Question about the picture
+		question: Vec<String>,
+	},
+	/// Save snapshots on motion and at a timelapse interval (requires --features camera)
+	#[cfg(feature = "camera")]
+	Watch {
+		/// Replay image files from this directory instead of using the camera
+		#[arg(long)]
+		frames: Option<String>,
+		/// Describe motion snapshots with the vision model (requires --features vision)
+		#[arg(long)]
+		describe: bool,
+	},
+	/// Camera utilities (requires --features camera)
+	#[cfg(feature = "camera")]
+	Camera {
+		#[command(subcommand)]
+		action: CameraAction,
+	},
+	/// Voice mode with wake word (requires --features stt-vosk with tts or tts-process)
+	#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+	Voice {
+		#[command(subcommand)]
+		action: Option<VoiceAction>,
+	},
+	/// Continuous voice chat with silence detection (requires --features stt-vosk with tts or tts-process)
+	#[cfg(all(feature = "stt-vosk", feature = "speech"))]
 	Voicechat,
 	/// Fetch a URL, summarize, and learn (requires --features web)
 	#[cfg(feature = "web")]
 	Browse { url: String },
+	/// Answer a question from a web search with cited sources (requires --features web and search_url)
+	#[cfg(feature = "web")]
+	Search { query: Vec<String> },
 	/// Periodically learn from configured URLs (requires --features web)
 	#[cfg(feature = "web")]
 	Learn,
+	/// Manage learn sources: import/export OPML, add, remove, list (requires --features web)
+	#[cfg(feature = "web")]
+	Sources {
+		#[command(subcommand)]
+		action: SourcesAction,
+	},
+	/// Summarize local files, directories or globs into memory (requires --features ingest)
+	#[cfg(feature = "ingest")]
+	Ingest {
+		#[arg(required = true)]
+		paths: Vec<String>,
+		/// Re-learn files even if unchanged since the last ingest
+		#[arg(long)]
+		force: bool,
+	},
+	/// Read text from images, or a camera frame, and learn it (requires --features ocr)
+	#[cfg(feature = "ocr")]
+	Ocr {
+		/// Image files; a camera frame is captured when none are given
+		paths: Vec<String>,
+	},
 	/// Execute a safe action (requires --features actions)
 	#[cfg(feature = "actions")]
-	Act { kind: String, arg1: Option<String>, arg2: Option<String> },
+	Act(Box<ActArgs>),
+}
+
+#[cfg(feature = "actions")]
+#[derive(clap::Args, Debug)]
+struct ActArgs {
+	/// open_url, launch, run, http, read, write, append, list, search, stat, mkdir, trash, history, undo
+	kind: String,
+	arg1: Option<String>,
+	arg2: Option<String>,
+	/// For search: only files containing this text
+	#[arg(long)]
+	contains: Option<String>,
+	/// For undo: revert the newest N changes (default 1)
+	#[arg(long, conflicts_with = "id")]
+	last: Option<usize>,
+	/// For undo: revert one change by the id shown in `act history`
+	#[arg(long)]
+	id: Option<u64>,
+	/// For run: working directory (inside allow_dirs)
+	#[arg(long)]
+	cwd: Option<std::path::PathBuf>,
+	/// For run: the program and its arguments, after `--`
+	#[arg(last = true)]
+	command: Vec<String>,
+	/// For http: a request header as "Name: value" (repeatable)
+	#[arg(long = "header")]
+	headers: Vec<String>,
+	/// For http: JSON request body
+	#[arg(long)]
+	json: Option<String>,
+	/// Validate and show what would happen without doing it
+	#[arg(long)]
+	dry_run: bool,
+}
+
+#[cfg(feature = "stt-vosk")]
+#[derive(Subcommand, Debug)]
+enum AudioAction {
+	/// List audio hosts, input/output devices and supported configs
+	Devices,
+}
+
+#[cfg(feature = "camera")]
+#[derive(Subcommand, Debug)]
+enum CameraAction {
+	/// List cameras with their index and supported capture formats
+	List,
+}
+
+#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+#[derive(Subcommand, Debug)]
+enum VoiceAction {
+	/// Record the wake word a few times and accept how the recognizer hears it
+	TrainWake {
+		#[arg(long, default_value_t = 3)]
+		count: usize,
+	},
+}
+
+#[cfg(feature = "web")]
+#[derive(Subcommand, Debug)]
+enum SourcesAction {
+	/// Import feeds from an OPML file
+	Import { file: String },
+	/// Export sources as OPML (stdout if no file given)
+	Export { file: Option<String> },
+	/// Add a page or feed source
+	Add {
+		url: String,
+		/// Treat the URL as an RSS/Atom/JSON feed
+		#[arg(long)]
+		feed: bool,
+		#[arg(long)]
+		title: Option<String>,
+		/// Fetch interval in seconds (defaults to learn_interval_secs)
+		#[arg(long)]
+		interval: Option<u64>,
+		#[arg(long = "tag")]
+		tags: Vec<String>,
+		/// Add the source disabled
+		#[arg(long)]
+		disabled: bool,
+	},
+	/// Remove a source by URL
+	Remove { url: String },
+	/// Enable a source by URL
+	Enable { url: String },
+	/// Disable a source by URL
+	Disable { url: String },
+	/// List sources with their last fetch status
+	List,
 }
 
 #[tokio::main]
@@ -59,28 +224,110 @@ async fn main() -> Result<()> {
 
 	match cli.command.unwrap_or(Commands::Run) {
 		Commands::Run => run_repl(settings).await?,
-		#[cfg(feature = "tts")]
-		Commands::Say { text } => modules::tts::speak(&settings, &text)?,
+		#[cfg(feature = "speech")]
+		Commands::Say { text, out: None } => modules::tts::speak(&settings, &text)?,
+		#[cfg(feature = "speech")]
+		Commands::Say { text, out: Some(out) } => {
+			modules::tts::render_to_file(&settings, &text, std::path::Path::new(&out))?;
+			println!("Saved speech to {}", out);
+		}
 		#[cfg(feature = "stt-vosk")]
 		Commands::Listen => {
-			let transcript = modules::stt::transcribe_once(settings.stt_model_path.as_deref())?;
+			let transcript = modules::stt::transcribe_utterance(&settings)?;
+			println!("{}", transcript);
+		}
+		#[cfg(feature = "stt-vosk")]
+		Commands::Transcribe { file, rate, channels } => {
+			use modules::audio::{AudioFormat, PcmSource, WavSource};
+			let model = modules::stt::load_model(&settings)?;
+			let transcript = if file == "-" {
+				modules::stt::transcribe_source(&model, &mut PcmSource::stdin(AudioFormat { sample_rate: rate, channels }), None)?
+			} else {
+				modules::stt::transcribe_source(&model, &mut WavSource::open(std::path::Path::new(&file))?, None)?
+			};
 			println!("{}", transcript);
 		}
+		#[cfg(feature = "stt-vosk")]
+		Commands::Audio { action: AudioAction::Devices } => print!("{}", modules::audio::describe_devices()?),
 		#[cfg(feature = "camera")]
 		Commands::Snapshot { output } => {
-			modules::camera::snapshot(&output)?;
+			modules::camera::snapshot(&settings, &output)?;
 			println!("Saved snapshot to {}", output);
 		}
-		#[cfg(all(feature = "stt-vosk", feature = "tts"))]
-		Commands::Voice => run_voice(settings).await?,
-		#[cfg(all(feature = "stt-vosk", feature = "tts"))]
+		#[cfg(feature = "vision")]
+		Commands::Look { image, question } => {
+			let picture = modules::vision::picture(&settings, image.as_deref().map(std::path::Path::new))?;
+			let question = question.join(" ");
+			let mut agent = agent::Agent::new(settings)?;
+			println!("{}", agent.look(&picture, Some(question.as_str()).filter(|q| !q.is_empty())).await?);
+		}
+		#[cfg(feature = "camera")]
+		Commands::Watch { frames, describe } => {
+			use modules::watch::{CameraFrames, DirFrames, FrameSource};
+			let mut source: Box<dyn FrameSource> = match frames {
+				Some(dir) => Box::new(DirFrames::open(std::path::Path::new(&dir), &settings)?),
+				None => Box::new(CameraFrames::open(&settings)?),
+			};
+			modules::watch::run(settings, source.as_mut(), describe).await?;
+		}
+		#[cfg(feature = "camera")]
+		Commands::Camera { action: CameraAction::List } => print!("{}", modules::camera::describe_devices()?),
+		#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+		Commands::Voice { action: None } => run_voice(settings).await?,
+		#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+		Commands::Voice { action: Some(VoiceAction::TrainWake { count }) } => {
+			let added = modules::voice::train_wake(&settings, count)?;
+			println!("Added {} wake word variant(s): {}", added.len(), added.join(", "));
+		}
+		#[cfg(all(feature = "stt-vosk", feature = "speech"))]
 		Commands::Voicechat => modules::voicechat::run(settings).await?,
 		#[cfg(feature = "web")]
 		Commands::Browse { url } => run_browse(settings, &url).await?,
 		#[cfg(feature = "web")]
+		Commands::Search { query } => {
+			let query = query.join(" ");
+			let mut agent = agent::Agent::new(settings)?;
+			println!("{}", agent.answer_with_search(&query, &query).await?);
+		}
+		#[cfg(feature = "web")]
 		Commands::Learn => run_learn_daemon(settings).await?,
+		#[cfg(feature = "web")]
+		Commands::Sources { action } => run_sources(settings, action)?,
+		#[cfg(feature = "ingest")]
+		Commands::Ingest { paths, force } => modules::ingest::run(settings, &paths, force).await?,
+		#[cfg(feature = "ocr")]
+		Commands::Ocr { paths } => modules::ocr::run(settings, &paths).await?,
 		#[cfg(feature = "actions")]
-		Commands::Act { kind, arg1, arg2 } => run_act(settings, &kind, arg1.as_deref(), arg2.as_deref())?,
+		Commands::Act(act) => {
+			let ActArgs { kind, arg1, arg2, contains, last, id, cwd, command, headers, json, dry_run } = *act;
+			let mut settings = settings;
+			if dry_run { settings.actions_mode = Some("plan".to_string()); }
+			let kind = kind.to_ascii_lowercase();
+			match kind.as_str() {
+				"history" => println!("{}", modules::undo::history()?),
+				"undo" if dry_run => return Err(anyhow::anyhow!("--dry-run is not supported for undo; see `milyai act history`")),
+				"undo" => match id {
+					Some(id) => println!("{}", modules::undo::undo_id(&settings, id)?),
+					None => for line in modules::undo::undo_last(&settings, last.unwrap_or(1))? { println!("{}", line); },
+				},
+				"run" => {
+					let (program, args) = command.split_first().ok_or_else(|| anyhow::anyhow!("usage: milyai act run [--cwd DIR] -- PROGRAM [ARGS...]"))?;
+					let action = modules::actions::Action::RunCommand { program: program.clone(), args: args.to_vec(), cwd };
+					println!("{}", modules::actions::execute(&settings, action).await?);
+				}
+				"http" => {
+					let (Some(method), Some(url)) = (arg1, arg2) else { return Err(anyhow::anyhow!("usage: milyai act http METHOD URL [--header 'Name: value'] [--json BODY]")) };
+					let headers = headers
+						.iter()
+						.map(|h| h.split_once(':').map(|(n, v)| (n.trim().to_string(), v.trim().to_string())).ok_or_else(|| anyhow::anyhow!("Header must look like 'Name: value', got '{}'", h)))
+						.collect::<Result<Vec<_>>>()?;
+					let body = json.as_deref().map(serde_json::from_str).transpose().map_err(|e| anyhow::anyhow!("--json is not valid JSON: {}", e))?;
+					let action = modules::actions::Action::HttpRequest { method, url, headers, body };
+					println!("{}", modules::actions::execute(&settings, action).await?);
+				}
+				_ => run_act(settings, &kind, arg1.as_deref(), arg2.as_deref(), contains).await?,
+			}
+		}
 	}
 
 	Ok(())
@@ -88,43 +335,81 @@ async fn main() -> Result<()> {
 
 async fn run_repl(settings: settings::Settings) -> Result<()> {
 	use std::io::{self, Write};
-	let speak_settings = settings.clone();
-	let mut agent = agent::Agent::new(settings)?;
+	#[cfg(feature = "speech")]
+	let speech = modules::tts::SpeechWorker::spawn(&settings).ok();
+	let mut agent = agent::Agent::new(settings.clone())?;
 	println!("MilyAI ready. Type 'exit' to quit.");
 	loop {
 		print!("> ");
 		io::stdout().flush()?;
 		let mut input = String::new();
 		io::stdin().read_line(&mut input)?;
+		// a new message interrupts the previous spoken reply
+		#[cfg(feature = "speech")]
+		if let Some(s) = &speech { s.cancel(); }
 		let msg = input.trim();
 		if msg.eq_ignore_ascii_case("exit") || msg.eq_ignore_ascii_case("quit") {
 			break;
 		}
-		let response = agent.respond(msg).await?;
-		println!("{}", response);
-		#[cfg(feature = "tts")]
-		{
-			let _ = modules::tts::speak(&speak_settings, &response);
+		// "/look" describes a camera frame, "/look <file>" an image file
+		#[cfg(feature = "vision")]
+		if msg == "/look" || msg.starts_with("/look ") {
+			let path = msg["/look".len()..].trim();
+			let described: Result<String> = async {
+				let picture = modules::vision::picture(&settings, (!path.is_empty()).then(|| std::path::Path::new(path)))?;
+				agent.look(&picture, None).await
+			}.await;
+			match described {
+				Ok(d) => {
+					println!("{}", d);
+					#[cfg(feature = "speech")]
+					if let Some(s) = &speech { s.say(&d); }
+				}
+				Err(e) => println!("Cannot look: {}", e),
+			}
+			continue;
 		}
+		agent.respond_streaming(msg, &mut |t| {
+			print!("{}", t);
+			let _ = io::stdout().flush();
+			#[cfg(feature = "speech")]
+			if let Some(s) = &speech { s.push(t); }
+		}).await?;
+		println!();
+		#[cfg(feature = "speech")]
+		if let Some(s) = &speech { s.finish(); }
 	}
 	Ok(())
 }
 
-#[cfg(all(feature = "stt-vosk", feature = "tts"))]
+#[cfg(all(feature = "stt-vosk", feature = "speech"))]
 async fn run_voice(settings: settings::Settings) -> Result<()> {
-	use std::time::Duration;
 	let mut agent = agent::Agent::new(settings.clone())?;
-	println!("Voice mode. Say 'Milly' to wake me. Ctrl+C to exit.");
+	let mut listener = modules::voice::WakeListener::new(&settings)?;
+	let speech = modules::tts::SpeechWorker::spawn(&settings)?;
+	let commands = modules::voicecmd::CommandTable::from_settings(&settings);
+	println!("Voice mode. Say '{}' to wake me. Ctrl+C to exit.", listener.wake_words().first().map(String::as_str).unwrap_or("milly"));
+	let mut barge_in: Option<String> = None;
+	let mut last_reply = String::new();
 	loop {
-		if let Some(query) = modules::voice::listen_for_wake_and_query(settings.stt_model_path.as_deref())? {
-			if query.trim().is_empty() { continue; }
-			let reply = agent.respond(&query).await?;
-			println!("You: {}\nMily: {}", query, reply);
-			let _ = modules::tts::speak(&settings, &reply);
-			// small cooldown to avoid re-triggering immediately
-			tokio::time::sleep(Duration::from_millis(800)).await;
+		// talking over a reply starts the next turn without the wake word
+		let query = match barge_in.take() {
+			Some(q) => q,
+			None => listener.next_query()?,
+		};
+		if query.trim().is_empty() { continue; }
+		if let Some(cmd) = commands.match_command(&query) {
+			println!("You: {} [{:?}]", query, cmd);
+			if let modules::voicecmd::Outcome::Exit = modules::voicecmd::apply(&cmd, &speech, &mut agent, &last_reply) { break; }
+			barge_in = listener.listen_during(&speech, &last_reply)?;
+			continue;
 		}
+		print!("You: {}\nMily: ", query);
+		let (reply, next) = listener.respond(&mut agent, &query, &speech).await?;
+		barge_in = next;
+		last_reply = reply;
 	}
+	Ok(())
 }
 
 #[cfg(feature = "web")]
@@ -138,47 +423,118 @@ async fn run_browse(settings: settings::Settings, url: &str) -> Result<()> {
 
 #[cfg(feature = "web")]
 async fn run_learn_daemon(settings: settings::Settings) -> Result<()> {
+	use modules::sources::{self, SourceList};
 	use tokio::time::{sleep, Duration};
 	let mut agent = agent::Agent::new(settings.clone())?;
-	let urls = settings.learn_urls.clone().unwrap_or_default();
-	let feeds = settings.learn_feeds.clone().unwrap_or_default();
-	if urls.is_empty() && feeds.is_empty() {
-		println!("No learn_sources configured in config.yaml");
+	// config.yaml sources are not persisted, so their fetch times live here
+	let mut config_sources = sources::from_settings(&settings);
+	let mut list = SourceList::load(&settings)?;
+	if config_sources.is_empty() && list.sources.is_empty() {
+		println!("No learn sources configured in config.yaml or sources.yaml");
 		return Ok(());
 	}
+	#[cfg(feature = "feeds")]
+	let mut seen = modules::feeds::SeenStore::open_default()?;
 	let interval = settings.learn_interval_secs.unwrap_or(3600);
-	println!("Learn daemon started. {} URLs, {} feeds, every {}s. Ctrl+C to stop.", urls.len(), feeds.len(), interval);
+	println!("Learn daemon started. {} sources, default every {}s. Ctrl+C to stop.", config_sources.len() + list.sources.len(), interval);
 	loop {
-		for u in &urls {
-			match modules::web::fetch_text(&settings, u).await {
-				Ok(text) => { let _ = agent.summarize_and_learn(u, &text).await; println!("Learned from {}", u); }
-				Err(e) => eprintln!("Fetch failed {}: {}", u, e),
+		// reload each round so `milyai sources add/remove` takes effect without a restart
+		list = SourceList::load(&settings)?;
+		let now = chrono::Utc::now();
+		for src in config_sources.iter_mut().chain(list.sources.iter_mut()) {
+			if !src.enabled || src.next_due(interval) > now { continue; }
+			#[cfg(feature = "feeds")]
+			let result = sources::learn_source(&mut agent, &settings, &mut seen, src).await;
+			#[cfg(not(feature = "feeds"))]
+			let result = sources::learn_source(&mut agent, &settings, src).await;
+			src.last_fetch = Some(chrono::Utc::now());
+			src.last_status = Some(match result {
+				Ok(status) => { println!("Learned from {} ({})", src.url, status); status }
+				Err(e) => { eprintln!("Fetch failed {}: {}", src.url, e); format!("error: {}", e) }
+			});
+		}
+		list.save_status()?;
+		let next = config_sources.iter().chain(list.sources.iter()).filter(|s| s.enabled).map(|s| s.next_due(interval)).min();
+		let wait = next.map(|t| (t - chrono::Utc::now()).num_seconds().max(1) as u64).unwrap_or(interval);
+		sleep(Duration::from_secs(wait)).await;
+	}
+}
+
+#[cfg(feature = "web")]
+fn run_sources(settings: settings::Settings, action: SourcesAction) -> Result<()> {
+	use modules::sources::{Source, SourceKind, SourceList};
+	let mut list = SourceList::load(&settings)?;
+	match action {
+		SourcesAction::Import { file } => {
+			let xml = std::fs::read_to_string(&file)?;
+			let added = list.import_opml(&xml)?;
+			list.save()?;
+			println!("Imported {} new sources from {}", added, file);
+		}
+		SourcesAction::Export { file } => {
+			let xml = list.export_opml()?;
+			match file {
+				Some(f) => { std::fs::write(&f, xml)?; println!("Exported {} sources to {}", list.sources.len(), f); }
+				None => println!("{}", xml),
 			}
 		}
-		#[cfg(feature = "feeds")]
-		for f in &feeds {
-			match modules::feeds::fetch_feed_text(f).await {
-				Ok(items) => {
-					for item in items.into_iter().take(5) {
-						let _ = agent.summarize_and_learn(f, &item).await;
-					}
-					println!("Learned from feed {}", f);
-				}
-				Err(e) => eprintln!("Feed failed {}: {}", f, e),
+		SourcesAction::Add { url, feed, title, interval, tags, disabled } => {
+			let mut source = Source::new(&url, if feed { SourceKind::Feed } else { SourceKind::Page });
+			source.title = title;
+			source.interval_secs = interval;
+			source.tags = tags;
+			source.enabled = !disabled;
+			list.add(source)?;
+			list.save()?;
+			println!("Added {}", url);
+		}
+		SourcesAction::Remove { url } => {
+			if !list.remove(&url) { return Err(anyhow::anyhow!("No such source: {}", url)); }
+			list.save()?;
+			println!("Removed {}", url);
+		}
+		SourcesAction::Enable { url } => {
+			list.find_mut(&url).ok_or_else(|| anyhow::anyhow!("No such source: {}", url))?.enabled = true;
+			list.save()?;
+			println!("Enabled {}", url);
+		}
+		SourcesAction::Disable { url } => {
+			list.find_mut(&url).ok_or_else(|| anyhow::anyhow!("No such source: {}", url))?.enabled = false;
+			list.save()?;
+			println!("Disabled {}", url);
+		}
+		SourcesAction::List => {
+			if list.sources.is_empty() { println!("No sources"); }
+			for s in &list.sources {
+				let kind = match s.kind { SourceKind::Feed => "feed", SourceKind::Page => "page" };
+				let state = if s.enabled { "" } else { " [disabled]" };
+				let tags = if s.tags.is_empty() { String::new() } else { format!(" #{}", s.tags.join(" #")) };
+				let interval = s.interval_secs.map(|i| format!(" every {}s", i)).unwrap_or_default();
+				let last = match (&s.last_fetch, &s.last_status) {
+					(Some(t), Some(st)) => format!(" (last {}: {})", t.to_rfc3339(), st),
+					_ => " (never fetched)".to_string(),
+				};
+				println!("{} {}{}{}{}{}", kind, s.url, state, interval, tags, last);
 			}
 		}
-		sleep(Duration::from_secs(interval)).await;
 	}
+	Ok(())
 }
 
 #[cfg(feature = "actions")]
-fn run_act(settings: settings::Settings, kind: &str, arg1: Option<&str>, arg2: Option<&str>) -> Result<()> {
+async fn run_act(settings: settings::Settings, kind: &str, arg1: Option<&str>, arg2: Option<&str>, contains: Option<String>) -> Result<()> {
 	use modules::actions::{self, Action};
-	let outcome = match kind.to_ascii_lowercase().as_str() {
-		"open_url" => actions::execute(&settings, Action::OpenUrl(arg1.ok_or_else(|| anyhow::anyhow!("url required"))?.to_string()))?,
-		"launch" => actions::execute(&settings, Action::LaunchApp { app: arg1.ok_or_else(|| anyhow::anyhow!("app required"))?.to_string(), args: arg2.map(|s| vec![s.to_string()]).unwrap_or_default() })?,
-		"read" => actions::execute(&settings, Action::ReadFile(arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into()))?,
-		"write" => actions::execute(&settings, Action::WriteFile { path: arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into(), content: arg2.unwrap_or("").to_string() })?,
+	let outcome = match kind {
+		"open_url" => actions::execute(&settings, Action::OpenUrl(arg1.ok_or_else(|| anyhow::anyhow!("url required"))?.to_string())).await?,
+		"launch" => actions::execute(&settings, Action::LaunchApp { app: arg1.ok_or_else(|| anyhow::anyhow!("app required"))?.to_string(), args: arg2.map(|s| vec![s.to_string()]).unwrap_or_default() }).await?,
+		"read" => actions::execute(&settings, Action::ReadFile(arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into())).await?,
+		"write" => actions::execute(&settings, Action::WriteFile { path: arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into(), content: arg2.unwrap_or("").to_string() }).await?,
+		"append" => actions::execute(&settings, Action::AppendFile { path: arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into(), content: arg2.unwrap_or("").to_string() }).await?,
+		"list" => actions::execute(&settings, Action::ListDir(arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into())).await?,
+		"search" => actions::execute(&settings, Action::SearchFiles { dir: arg1.ok_or_else(|| anyhow::anyhow!("dir required"))?.into(), name: arg2.unwrap_or("*").to_string(), contains }).await?,
+		"stat" => actions::execute(&settings, Action::Stat(arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into())).await?,
+		"mkdir" => actions::execute(&settings, Action::CreateDir(arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into())).await?,
+		"trash" => actions::execute(&settings, Action::MoveToTrash(arg1.ok_or_else(|| anyhow::anyhow!("path required"))?.into())).await?,
 		_ => return Err(anyhow::anyhow!("unknown kind")),
 	};
 	println!("{}", outcome);
@@ -14,6 +14,31 @@ pub struct MessageRecord {
 	pub text: String,
 }
 
+#[cfg(test)]
+thread_local! {
+	static TEST_DATA_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Make `data_dir` return `dir` on the calling test's thread, keeping tests off the real data.
+#[cfg(test)]
+#[cfg_attr(not(feature = "actions"), allow(dead_code))]
+pub fn set_test_data_dir(dir: &std::path::Path) {
+	TEST_DATA_DIR.with(|d| *d.borrow_mut() = Some(dir.to_path_buf()));
+}
+
+/// App data directory (created on demand); memory and other state files live here.
+pub fn data_dir() -> Result<PathBuf> {
+	#[cfg(test)]
+	if let Some(dir) = TEST_DATA_DIR.with(|d| d.borrow().clone()) {
+		fs::create_dir_all(&dir)?;
+		return Ok(dir);
+	}
+	let proj = ProjectDirs::from("com", "MilyAI", "milyai").expect("dirs");
+	let dir = proj.data_dir().to_path_buf();
+	fs::create_dir_all(&dir)?;
+	Ok(dir)
+}
+
 pub struct MemoryStore {
 	path: PathBuf,
 	inner: Arc<Mutex<()>>, // simple file lock
@@ -21,9 +46,7 @@ pub struct MemoryStore {
 
 impl MemoryStore {
 	pub fn new(settings: &Settings) -> Result<Self> {
-		let proj = ProjectDirs::from("com", "MilyAI", "milyai").expect("dirs");
-		let data_dir = proj.data_dir();
-		fs::create_dir_all(data_dir)?;
+		let data_dir = data_dir()?;
 		let path = settings
 			.memory_path
 			.clone()
@@ -1,42 +1,366 @@
 #![cfg(feature = "actions")]
 use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use crate::memory;
+use crate::modules::command;
+use crate::modules::policy::{Access, PathPolicy};
+use crate::modules::undo;
 use crate::settings::Settings;
 
+const MAX_LIST_ENTRIES: usize = 500;
+const MAX_SEARCH_RESULTS: usize = 200;
+const HTTP_TIMEOUT_SECS: u64 = 30;
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+const DEFAULT_HTTP_METHODS: &[&str] = &["GET", "HEAD"];
+
 pub enum Action {
 	OpenUrl(String),
 	LaunchApp { app: String, args: Vec<String> },
+	/// Run an allowed program to completion and return its output; `cwd` defaults to the first allow_dirs entry
+	RunCommand { program: String, args: Vec<String>, cwd: Option<PathBuf> },
 	ReadFile(PathBuf),
 	WriteFile { path: PathBuf, content: String },
+	AppendFile { path: PathBuf, content: String },
+	ListDir(PathBuf),
+	/// Files under `dir` whose name matches the glob `name`, optionally containing `contains` (case-insensitive)
+	SearchFiles { dir: PathBuf, name: String, contains: Option<String> },
+	Stat(PathBuf),
+	CreateDir(PathBuf),
+	/// Recoverable delete into the app's trash directory
+	MoveToTrash(PathBuf),
+	/// Call an HTTP service (e.g. a local REST API); the reply is the status and the start of the body
+	HttpRequest { method: String, url: String, headers: Vec<(String, String)>, body: Option<serde_json::Value> },
+}
+
+/// How `execute` treats an action, from `actions_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+	/// Validate and describe, change nothing
+	Plan,
+	/// Describe actions with side effects and ask before running them
+	Confirm,
+	Auto,
+}
+
+impl Mode {
+	pub fn from_settings(settings: &Settings) -> Result<Self> {
+		match settings.actions_mode.as_deref() {
+			Some("plan") => Ok(Mode::Plan),
+			Some("confirm") => Ok(Mode::Confirm),
+			Some("auto") => Ok(Mode::Auto),
+			Some(other) => Err(anyhow!("Unknown actions_mode '{}' (plan, confirm or auto)", other)),
+			None if settings.actions_auto_confirm == Some(true) => Ok(Mode::Auto),
+			None => Ok(Mode::Confirm),
+		}
+	}
 }
 
-pub fn execute(settings: &Settings, action: Action) -> Result<String> {
+/// Decide whether a validated action runs. Returns the reply to give instead when it does not:
+/// the plan in plan mode, or a note when the user declines.
+fn gate(mode: Mode, side_effects: bool, plan: impl FnOnce() -> Result<String>) -> Result<Option<String>> {
+	match mode {
+		Mode::Plan => Ok(Some(format!("Would {}", plan()?))),
+		Mode::Confirm if side_effects => {
+			println!("About to {}", plan()?);
+			print!("Proceed? [y/N] ");
+			std::io::stdout().flush()?;
+			let mut answer = String::new();
+			std::io::stdin().read_line(&mut answer)?;
+			if answer.trim().eq_ignore_ascii_case("y") || answer.trim().eq_ignore_ascii_case("yes") { Ok(None) } else { Ok(Some("Cancelled".to_string())) }
+		}
+		_ => Ok(None),
+	}
+}
+
+pub async fn execute(settings: &Settings, action: Action) -> Result<String> {
+	let mode = Mode::from_settings(settings)?;
 	match action {
 		Action::OpenUrl(url) => {
 			check_url_allowed(settings, &url)?;
+			if let Some(reply) = gate(mode, true, || Ok(format!("open URL: {}", url)))? { return Ok(reply); }
 			webbrowser::open(&url)?;
 			Ok(format!("Opened URL: {}", url))
 		}
 		Action::LaunchApp { app, args } => {
 			check_app_allowed(settings, &app)?;
+			if let Some(reply) = gate(mode, true, || Ok(format!("launch: {}", command::command_line(&app, &args))))? { return Ok(reply); }
 			std::process::Command::new(&app).args(&args).spawn()?;
 			Ok(format!("Launched: {} {}", app, args.join(" ")))
 		}
+		Action::RunCommand { program, args, cwd } => {
+			check_app_allowed(settings, &program)?;
+			command::check_args(settings, &program, &args)?;
+			let cwd = match cwd {
+				Some(d) => d,
+				None => settings.allow_dirs.as_ref().and_then(|d| d.first().cloned()).ok_or_else(|| anyhow!("No allow_dirs configured"))?,
+			};
+			let cwd = PathPolicy::from_settings(settings)?.check(&cwd, Access::Read)?;
+			if !cwd.is_dir() { return Err(anyhow!("{} is not a directory", cwd.display())); }
+			let plan = || Ok(format!("run: {}\nin: {}\ntimeout: {} s, environment cleared", command::command_line(&program, &args), cwd.display(), command::timeout(settings).as_secs()));
+			if let Some(reply) = gate(mode, true, plan)? { return Ok(reply); }
+			command::run(settings, &program, &args, &cwd)
+		}
 		Action::ReadFile(path) => {
-			check_path_allowed(settings, &path)?;
+			let policy = PathPolicy::from_settings(settings)?;
+			let path = policy.check(&path, Access::Read)?;
+			policy.check_size(&path)?;
+			if let Some(reply) = gate(mode, false, || Ok(format!("read: {}", path.display())))? { return Ok(reply); }
 			let content = fs::read_to_string(&path)?;
 			Ok(content)
 		}
 		Action::WriteFile { path, content } => {
-			check_path_allowed(settings, &path)?;
+			let policy = PathPolicy::from_settings(settings)?;
+			let path = policy.check(&path, Access::Write)?;
+			policy.check_len(content.len() as u64)?;
+			let plan = || -> Result<String> {
+				if !path.exists() { return Ok(format!("create: {}\n{}", path.display(), diff_preview("", &content))); }
+				policy.check_size(&path)?;
+				let old = fs::read_to_string(&path)?;
+				Ok(format!("overwrite: {} (current content backed up for undo)\n{}", path.display(), diff_preview(&old, &content)))
+			};
+			if let Some(reply) = gate(mode, true, plan)? { return Ok(reply); }
+			let change = undo::snapshot_file(&path)?;
 			fs::write(&path, content.as_bytes())?;
-			Ok(format!("Wrote: {}", path.display()))
+			let id = undo::record(settings, "write", &path, change)?;
+			Ok(format!("Wrote: {} (undo with `act undo --id {}`)", path.display(), id))
+		}
+		Action::AppendFile { path, content } => {
+			let policy = PathPolicy::from_settings(settings)?;
+			let path = policy.check(&path, Access::Write)?;
+			let existing = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+			policy.check_len(existing + content.len() as u64)?;
+			let plan = || Ok(format!("append {} bytes to: {}\n{}", content.len(), path.display(), diff_preview("", &content)));
+			if let Some(reply) = gate(mode, true, plan)? { return Ok(reply); }
+			let change = undo::snapshot_file(&path)?;
+			fs::OpenOptions::new().create(true).append(true).open(&path)?.write_all(content.as_bytes())?;
+			let id = undo::record(settings, "append", &path, change)?;
+			Ok(format!("Appended {} bytes to: {} (undo with `act undo --id {}`)", content.len(), path.display(), id))
+		}
+		Action::ListDir(path) => {
+			let policy = PathPolicy::from_settings(settings)?;
+			let path = policy.check(&path, Access::Read)?;
+			if let Some(reply) = gate(mode, false, || Ok(format!("list: {}", path.display())))? { return Ok(reply); }
+			list_dir(&policy, &path)
+		}
+		Action::SearchFiles { dir, name, contains } => {
+			let policy = PathPolicy::from_settings(settings)?;
+			let dir = policy.check(&dir, Access::Read)?;
+			if let Some(reply) = gate(mode, false, || Ok(format!("search: {} for {}", dir.display(), name)))? { return Ok(reply); }
+			search_files(&policy, &dir, &name, contains.as_deref())
+		}
+		Action::Stat(path) => {
+			let path = PathPolicy::from_settings(settings)?.check_entry(&path, Access::Read)?;
+			if let Some(reply) = gate(mode, false, || Ok(format!("stat: {}", path.display())))? { return Ok(reply); }
+			stat(&path)
+		}
+		Action::CreateDir(path) => {
+			let path = PathPolicy::from_settings(settings)?.check(&path, Access::Write)?;
+			let top = path.ancestors().take_while(|p| !p.exists()).last().map(Path::to_path_buf);
+			let plan = || Ok(match &top {
+				Some(top) if top != &path => format!("create directory: {} (starting at {})", path.display(), top.display()),
+				Some(_) => format!("create directory: {}", path.display()),
+				None => format!("leave existing directory: {}", path.display()),
+			});
+			if let Some(reply) = gate(mode, true, plan)? { return Ok(reply); }
+			fs::create_dir_all(&path)?;
+			if let Some(top) = top {
+				undo::record(settings, "mkdir", &path, undo::Change::Dir { top })?;
+			}
+			Ok(format!("Created directory: {}", path.display()))
+		}
+		Action::MoveToTrash(path) => {
+			let policy = PathPolicy::from_settings(settings)?;
+			let path = policy.check_entry(&path, Access::Write)?;
+			if policy.is_root(&path) { return Err(anyhow!("{} is an allow_dirs root and cannot be trashed", path.display())); }
+			if !path.exists() && fs::symlink_metadata(&path).is_err() { return Err(anyhow!("{} does not exist", path.display())); }
+			if let Some(reply) = gate(mode, true, || Ok(format!("move to trash: {}\n{}", path.display(), stat(&path)?)))? { return Ok(reply); }
+			let entry = move_to_trash(&path)?;
+			undo::record(settings, "trash", &entry.original, undo::Change::Trash { stored: entry.stored.clone() })?;
+			Ok(format!("Moved to trash: {} (as {})", path.display(), entry.stored.display()))
+		}
+		Action::HttpRequest { method, url, headers, body } => {
+			let method = method.to_uppercase();
+			check_http_allowed(settings, &method, &url, &headers)?;
+			let plan = || -> Result<String> {
+				let mut plan = format!("send: {} {}", method, url);
+				for (name, value) in &headers { plan.push_str(&format!("\n{}: {}", name, value)); }
+				if let Some(body) = &body { plan.push_str(&format!("\n\n{}", serde_json::to_string_pretty(body)?)); }
+				Ok(plan)
+			};
+			// reads are treated like file reads; anything else may change state on the other end
+			let side_effects = !matches!(method.as_str(), "GET" | "HEAD");
+			if let Some(reply) = gate(mode, side_effects, plan)? { return Ok(reply); }
+			http_request(&method, &url, &headers, body.as_ref()).await
 		}
 	}
 }
 
+const MAX_PREVIEW_LINES: usize = 40;
+
+/// The changed middle of two texts as -/+ lines, after skipping the lines they share at either end.
+fn diff_preview(old: &str, new: &str) -> String {
+	let old: Vec<&str> = old.lines().collect();
+	let new: Vec<&str> = new.lines().collect();
+	let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+	let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+	let removed = &old[prefix..old.len() - suffix];
+	let added = &new[prefix..new.len() - suffix];
+	if removed.is_empty() && added.is_empty() { return "(no change)".to_string(); }
+	let mut out = vec![format!("@@ line {} @@", prefix + 1)];
+	let lines = removed.iter().map(|l| format!("-{}", l)).chain(added.iter().map(|l| format!("+{}", l)));
+	out.extend(lines.take(MAX_PREVIEW_LINES));
+	let total = removed.len() + added.len();
+	if total > MAX_PREVIEW_LINES { out.push(format!("... {} more changed lines", total - MAX_PREVIEW_LINES)); }
+	out.join("\n")
+}
+
+fn list_dir(policy: &PathPolicy, path: &Path) -> Result<String> {
+	let mut entries: Vec<(bool, String, u64)> = fs::read_dir(path)?
+		.filter_map(|e| e.ok())
+		.filter(|e| !policy.is_denied(&e.path()))
+		.map(|e| {
+			let meta = e.metadata().ok();
+			(meta.as_ref().is_some_and(|m| m.is_dir()), e.file_name().to_string_lossy().to_string(), meta.map(|m| m.len()).unwrap_or(0))
+		})
+		.collect();
+	// directories first, then by name
+	entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+	let total = entries.len();
+	let mut out: Vec<String> = entries
+		.into_iter()
+		.take(MAX_LIST_ENTRIES)
+		.map(|(is_dir, name, len)| if is_dir { format!("{}/", name) } else { format!("{} ({} bytes)", name, len) })
+		.collect();
+	if total > MAX_LIST_ENTRIES { out.push(format!("... {} more", total - MAX_LIST_ENTRIES)); }
+	Ok(out.join("\n"))
+}
+
+fn search_files(policy: &PathPolicy, dir: &Path, name: &str, contains: Option<&str>) -> Result<String> {
+	let pattern = glob::Pattern::new(name)?;
+	let needle = contains.map(|c| c.to_lowercase());
+	let mut found = Vec::new();
+	for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+		if !entry.file_type().is_file() || !pattern.matches(&entry.file_name().to_string_lossy()) { continue; }
+		// symlinks inside the tree may point elsewhere
+		if policy.check(entry.path(), Access::Read).is_err() { continue; }
+		if let Some(needle) = &needle {
+			if entry.metadata().map(|m| m.len()).unwrap_or(u64::MAX) > policy.max_file_bytes { continue; }
+			let mut text = String::new();
+			if fs::File::open(entry.path()).and_then(|mut f| f.read_to_string(&mut text)).is_err() { continue; }
+			if !text.to_lowercase().contains(needle.as_str()) { continue; }
+		}
+		found.push(entry.path().display().to_string());
+		if found.len() >= MAX_SEARCH_RESULTS {
+			found.push(format!("... stopped at {} results", MAX_SEARCH_RESULTS));
+			break;
+		}
+	}
+	if found.is_empty() { return Ok("No matching files".to_string()); }
+	Ok(found.join("\n"))
+}
+
+fn stat(path: &Path) -> Result<String> {
+	let meta = fs::symlink_metadata(path)?;
+	let kind = if meta.file_type().is_symlink() { "symlink" } else if meta.is_dir() { "directory" } else { "file" };
+	let modified = meta.modified().ok().map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_else(|| "unknown".to_string());
+	Ok(format!(
+		"{}\nkind: {}\nsize: {} bytes\nmodified: {}\nread-only: {}",
+		path.display(), kind, meta.len(), modified, meta.permissions().readonly()
+	))
+}
+
+/// Something moved to the trash, and where it came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+	pub original: PathBuf,
+	pub stored: PathBuf,
+	pub trashed_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn trash_dir() -> Result<PathBuf> {
+	let dir = memory::data_dir()?.join("trash");
+	fs::create_dir_all(&dir)?;
+	Ok(dir)
+}
+
+/// Entries still in the trash, oldest first.
+pub fn trash_entries() -> Result<Vec<TrashEntry>> {
+	match fs::read_to_string(trash_dir()?.join("index.json")) {
+		Ok(s) => Ok(serde_json::from_str(&s)?),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+		Err(e) => Err(e.into()),
+	}
+}
+
+fn move_to_trash(path: &Path) -> Result<TrashEntry> {
+	let original = path.to_path_buf();
+	let name = original.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "item".to_string());
+	let now = chrono::Utc::now();
+	let stored = trash_dir()?.join(format!("{}-{}", now.format("%Y%m%d-%H%M%S%.3f"), name));
+	move_path(&original, &stored)?;
+	let entry = TrashEntry { original, stored, trashed_at: now };
+	let mut entries = trash_entries()?;
+	entries.push(entry.clone());
+	save_trash_entries(&entries)?;
+	Ok(entry)
+}
+
+/// Delete for good the trashed items whose stored copy is not in `keep`, i.e. whose undo
+/// journal entry has expired.
+pub fn purge_trash(keep: &HashSet<&PathBuf>) -> Result<()> {
+	let (kept, expired): (Vec<TrashEntry>, Vec<TrashEntry>) = trash_entries()?.into_iter().partition(|e| keep.contains(&e.stored));
+	if expired.is_empty() { return Ok(()); }
+	for entry in &expired {
+		match fs::symlink_metadata(&entry.stored) {
+			Ok(meta) if meta.is_dir() => fs::remove_dir_all(&entry.stored)?,
+			Ok(_) => fs::remove_file(&entry.stored)?,
+			Err(_) => {}
+		}
+	}
+	save_trash_entries(&kept)
+}
+
+fn save_trash_entries(entries: &[TrashEntry]) -> Result<()> {
+	fs::write(trash_dir()?.join("index.json"), serde_json::to_string_pretty(entries)?)?;
+	Ok(())
+}
+
+/// Move a trashed item back to `original`, refusing to overwrite anything now there.
+pub fn restore_from_trash(stored: &Path, original: &Path) -> Result<()> {
+	if original.exists() { return Err(anyhow!("{} exists again; move it away first", original.display())); }
+	if !stored.exists() { return Err(anyhow!("{} is no longer in the trash", stored.display())); }
+	move_path(stored, original)?;
+	let mut entries = trash_entries()?;
+	entries.retain(|e| e.stored != stored);
+	save_trash_entries(&entries)
+}
+
+/// Rename, falling back to copy and delete when `to` is on another filesystem.
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+	if fs::rename(from, to).is_ok() { return Ok(()); }
+	copy_and_remove(from, to)
+}
+
+fn copy_and_remove(from: &Path, to: &Path) -> Result<()> {
+	if from.is_dir() {
+		for entry in walkdir::WalkDir::new(from) {
+			let entry = entry?;
+			let target = to.join(entry.path().strip_prefix(from)?);
+			if entry.file_type().is_dir() { fs::create_dir_all(&target)?; } else { fs::copy(entry.path(), &target)?; }
+		}
+		fs::remove_dir_all(from)?;
+	} else {
+		fs::copy(from, to)?;
+		fs::remove_file(from)?;
+	}
+	Ok(())
+}
+
 fn check_app_allowed(settings: &Settings, app: &str) -> Result<()> {
 	let allow = settings.allow_apps.as_ref().ok_or_else(|| anyhow!("No allow_apps configured"))?;
 	if !allow.iter().any(|a| a.eq_ignore_ascii_case(app)) {
@@ -45,22 +369,310 @@ fn check_app_allowed(settings: &Settings, app: &str) -> Result<()> {
 	Ok(())
 }
 
-fn check_path_allowed(settings: &Settings, path: &Path) -> Result<()> {
-	let dirs = settings.allow_dirs.as_ref().ok_or_else(|| anyhow!("No allow_dirs configured"))?;
-	let canon = dunce::canonicalize(path).unwrap_or(path.to_path_buf());
-	for d in dirs {
-		let cd = dunce::canonicalize(d).unwrap_or(d.clone());
-		if canon.starts_with(&cd) { return Ok(()); }
-	}
-	Err(anyhow!("Path not allowed"))
-}
-
 fn check_url_allowed(settings: &Settings, url: &str) -> Result<()> {
 	use url::Url;
-	let host = Url::parse(url).ok().and_then(|u| u.host_str().map(|s| s.to_string()));
+	let host = Url::parse(url).ok().and_then(|u| u.host_str().map(|s| s.to_lowercase()));
 	if host.is_none() { return Ok(()); }
 	let host = host.unwrap();
-	if let Some(deny) = &settings.deny_domains { if deny.iter().any(|d| host.ends_with(d)) { return Err(anyhow!("Domain denied")); } }
-	if let Some(allow) = &settings.allow_domains { if !allow.is_empty() && !allow.iter().any(|a| host.ends_with(a)) { return Err(anyhow!("Domain not in allowlist")); } }
+	// the domain itself or a subdomain, so "rust-lang.org" does not admit "evil-rust-lang.org"
+	let covers = |d: &String| { let d = d.to_lowercase(); host == d || host.ends_with(&format!(".{}", d)) };
+	if let Some(deny) = &settings.deny_domains { if deny.iter().any(covers) { return Err(anyhow!("Domain denied")); } }
+	if let Some(allow) = &settings.allow_domains { if !allow.is_empty() && !allow.iter().any(covers) { return Err(anyhow!("Domain not in allowlist")); } }
+	Ok(())
+}
+
+fn check_http_allowed(settings: &Settings, method: &str, url: &str, headers: &[(String, String)]) -> Result<()> {
+	let parsed = url::Url::parse(url).map_err(|e| anyhow!("Bad URL {}: {}", url, e))?;
+	if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+		return Err(anyhow!("Only http(s) URLs with a host can be requested"));
+	}
+	check_url_allowed(settings, url)?;
+	let allowed = match &settings.allow_http_methods {
+		Some(methods) => methods.iter().any(|m| m.eq_ignore_ascii_case(method)),
+		None => DEFAULT_HTTP_METHODS.contains(&method),
+	};
+	if !allowed { return Err(anyhow!("HTTP method {} not in allow_http_methods", method)); }
+	// Host would let a request reach a different site than the one checked above
+	if headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("host")) { return Err(anyhow!("The Host header cannot be set")); }
 	Ok(())
-} 
\ No newline at end of file
+}
+
+async fn http_request(method: &str, url: &str, headers: &[(String, String)], body: Option<&serde_json::Value>) -> Result<String> {
+	// redirects are not followed, since their target never went through the domain policy
+	let client = reqwest::Client::builder()
+		.redirect(reqwest::redirect::Policy::none())
+		.timeout(std::time::Duration::from_secs(HTTP_TIMEOUT_SECS))
+		.build()?;
+	let mut req = client.request(reqwest::Method::from_bytes(method.as_bytes())?, url);
+	for (name, value) in headers { req = req.header(name, value); }
+	if let Some(body) = body { req = req.json(body); }
+	let mut resp = req.send().await?;
+	let mut out = format!("HTTP {}", resp.status());
+	if let Some(location) = resp.headers().get(reqwest::header::LOCATION).and_then(|v| v.to_str().ok()) {
+		out.push_str(&format!("\nLocation: {}", location));
+	}
+	if let Some(kind) = resp.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+		out.push_str(&format!("\nContent-Type: {}", kind));
+	}
+	let mut body = Vec::new();
+	let mut cut = false;
+	while let Some(chunk) = resp.chunk().await? {
+		let room = MAX_RESPONSE_BYTES - body.len();
+		body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+		if chunk.len() > room { cut = true; break; }
+	}
+	if !body.is_empty() { out.push_str(&format!("\n\n{}", String::from_utf8_lossy(&body))); }
+	if cut { out.push_str(&format!("\n[truncated at {} bytes]", MAX_RESPONSE_BYTES)); }
+	Ok(out)
+} 
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::BufRead;
+	use std::net::TcpListener;
+	use std::sync::{Arc, Mutex};
+	use tempfile::TempDir;
+
+	/// An allowed root and a private data dir in a fresh temp dir, with actions running unasked.
+	fn sandbox() -> (TempDir, PathBuf, Settings) {
+		let tmp = tempfile::tempdir().unwrap();
+		let base = fs::canonicalize(tmp.path()).unwrap();
+		memory::set_test_data_dir(&base.join("data"));
+		let root = base.join("root");
+		fs::create_dir(&root).unwrap();
+		let settings = Settings { allow_dirs: Some(vec![root.clone()]), actions_mode: Some("auto".to_string()), ..Default::default() };
+		(tmp, root, settings)
+	}
+
+	/// Serves /redirect (302 to /target), /big (a body over the limit) and 200 "ok" otherwise,
+	/// recording the request line of everything that arrives.
+	fn stub_server() -> (String, Arc<Mutex<Vec<String>>>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let base = format!("http://{}", listener.local_addr().unwrap());
+		let seen = Arc::new(Mutex::new(Vec::new()));
+		let (log, target) = (seen.clone(), format!("{}/target", base));
+		std::thread::spawn(move || {
+			for stream in listener.incoming() {
+				let Ok(mut stream) = stream else { continue };
+				let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+				let mut line = String::new();
+				reader.read_line(&mut line).unwrap();
+				loop {
+					let mut header = String::new();
+					if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" { break; }
+				}
+				let line = line.trim_end().to_string();
+				log.lock().unwrap().push(line.clone());
+				let (status, extra, body) = match line.split(' ').nth(1) {
+					Some("/redirect") => ("302 Found", format!("Location: {}\r\n", target), String::new()),
+					Some("/big") => ("200 OK", String::new(), "x".repeat(MAX_RESPONSE_BYTES + 1000)),
+					_ => ("200 OK", String::new(), "ok".to_string()),
+				};
+				let reply = format!("HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", status, extra, body.len(), body);
+				let _ = stream.write_all(reply.as_bytes());
+			}
+		});
+		(base, seen)
+	}
+
+	fn get(url: String, headers: Vec<(String, String)>) -> Action {
+		Action::HttpRequest { method: "GET".to_string(), url, headers, body: None }
+	}
+
+	#[tokio::test]
+	async fn http_request_follows_policy() {
+		let (base, seen) = stub_server();
+		let settings = Settings { allow_domains: Some(vec!["127.0.0.1".to_string()]), actions_mode: Some("auto".to_string()), ..Default::default() };
+
+		let reply = execute(&settings, get(format!("{}/hello", base), vec![])).await.unwrap();
+		assert!(reply.starts_with("HTTP 200 OK"), "{}", reply);
+		assert!(reply.ends_with("\n\nok"), "{}", reply);
+
+		// only GET and HEAD unless allow_http_methods says otherwise
+		let post = Action::HttpRequest { method: "post".to_string(), url: format!("{}/submit", base), headers: vec![], body: Some(serde_json::json!({"a": 1})) };
+		let err = execute(&settings, post).await.unwrap_err();
+		assert!(err.to_string().contains("not in allow_http_methods"), "{}", err);
+
+		let err = execute(&settings, get(format!("{}/hello", base), vec![("host".to_string(), "example.com".to_string())])).await.unwrap_err();
+		assert!(err.to_string().contains("Host header"), "{}", err);
+
+		// the redirect is reported, not followed
+		let reply = execute(&settings, get(format!("{}/redirect", base), vec![])).await.unwrap();
+		assert!(reply.starts_with("HTTP 302 Found"), "{}", reply);
+		assert!(reply.contains(&format!("Location: {}/target", base)), "{}", reply);
+
+		let reply = execute(&settings, get(format!("{}/big", base), vec![])).await.unwrap();
+		assert_eq!(reply.matches('x').count(), MAX_RESPONSE_BYTES);
+		assert!(reply.ends_with(&format!("[truncated at {} bytes]", MAX_RESPONSE_BYTES)), "{}", &reply[reply.len() - 60..]);
+
+		let other = Settings { allow_domains: Some(vec!["example.com".to_string()]), ..settings.clone() };
+		assert!(execute(&other, get(format!("{}/hello", base), vec![])).await.is_err());
+
+		let seen = seen.lock().unwrap().clone();
+		assert_eq!(seen, vec!["GET /hello HTTP/1.1", "GET /redirect HTTP/1.1", "GET /big HTTP/1.1"]);
+	}
+
+	#[tokio::test]
+	async fn list_dir_hides_denied_entries() {
+		let (_tmp, root, settings) = sandbox();
+		fs::create_dir_all(root.join(".ssh")).unwrap();
+		fs::create_dir(root.join("sub")).unwrap();
+		fs::write(root.join(".env"), "TOKEN=1").unwrap();
+		fs::write(root.join("id.key"), "k").unwrap();
+		fs::write(root.join("b.txt"), "hi").unwrap();
+		let reply = execute(&settings, Action::ListDir(root.clone())).await.unwrap();
+		assert_eq!(reply, "sub/\nb.txt (2 bytes)");
+		assert!(execute(&settings, Action::ListDir(root.join(".ssh"))).await.is_err());
+	}
+
+	#[cfg(unix)]
+	#[tokio::test]
+	async fn search_files_skips_large_and_escaping_files() {
+		let (tmp, root, settings) = sandbox();
+		let settings = Settings { actions_max_file_bytes: Some(64), ..settings };
+		let outside = tmp.path().join("outside.txt");
+		fs::write(&outside, "hello world").unwrap();
+		fs::create_dir(root.join("docs")).unwrap();
+		fs::write(root.join("docs/small.txt"), "Hello World").unwrap();
+		fs::write(root.join("docs/big.txt"), format!("{} world", "x".repeat(100))).unwrap();
+		fs::write(root.join("docs/other.md"), "world").unwrap();
+		std::os::unix::fs::symlink(&outside, root.join("docs/link.txt")).unwrap();
+		let search = |contains: Option<&str>| Action::SearchFiles { dir: root.clone(), name: "*.txt".to_string(), contains: contains.map(str::to_string) };
+
+		let reply = execute(&settings, search(Some("WORLD"))).await.unwrap();
+		assert_eq!(reply, root.join("docs/small.txt").display().to_string());
+		let reply = execute(&settings, search(None)).await.unwrap();
+		let mut names: Vec<&str> = reply.lines().collect();
+		names.sort();
+		assert_eq!(names, [root.join("docs/big.txt").display().to_string(), root.join("docs/small.txt").display().to_string()]);
+		assert_eq!(execute(&settings, search(Some("absent"))).await.unwrap(), "No matching files");
+	}
+
+	#[tokio::test]
+	async fn append_stat_and_create_dir() {
+		let (_tmp, root, settings) = sandbox();
+		let file = root.join("log.txt");
+		let append = |text: &str| Action::AppendFile { path: file.clone(), content: text.to_string() };
+		execute(&settings, append("one\n")).await.unwrap();
+		let reply = execute(&settings, append("two\n")).await.unwrap();
+		assert!(reply.starts_with("Appended 4 bytes to: "), "{}", reply);
+		assert_eq!(fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+		let small = Settings { actions_max_file_bytes: Some(10), ..settings.clone() };
+		assert!(execute(&small, append("three\n")).await.unwrap_err().to_string().contains("byte limit"));
+
+		let reply = execute(&settings, Action::Stat(file.clone())).await.unwrap();
+		assert!(reply.contains("kind: file\nsize: 8 bytes"), "{}", reply);
+
+		let dir = root.join("a/b");
+		assert_eq!(execute(&settings, Action::CreateDir(dir.clone())).await.unwrap(), format!("Created directory: {}", dir.display()));
+		assert!(dir.is_dir());
+		assert!(execute(&settings, Action::Stat(dir.clone())).await.unwrap().contains("kind: directory"));
+		// nothing new to undo when the directory already exists
+		let before = undo::history().unwrap();
+		execute(&settings, Action::CreateDir(dir)).await.unwrap();
+		assert_eq!(undo::history().unwrap(), before);
+		assert!(execute(&settings, Action::Stat(root.join("../outside"))).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn move_to_trash_and_restore() {
+		let (_tmp, root, settings) = sandbox();
+		let err = execute(&settings, Action::MoveToTrash(root.clone())).await.unwrap_err();
+		assert!(err.to_string().contains("allow_dirs root"), "{}", err);
+		let err = execute(&settings, Action::MoveToTrash(root.join("missing.txt"))).await.unwrap_err();
+		assert!(err.to_string().contains("does not exist"), "{}", err);
+
+		let file = root.join("old.txt");
+		fs::write(&file, "data").unwrap();
+		execute(&settings, Action::MoveToTrash(file.clone())).await.unwrap();
+		assert!(!file.exists());
+		let entries = trash_entries().unwrap();
+		assert_eq!(entries.len(), 1);
+		assert_eq!(entries[0].original, file);
+		assert_eq!(fs::read_to_string(&entries[0].stored).unwrap(), "data");
+
+		fs::write(&file, "newer").unwrap();
+		let err = restore_from_trash(&entries[0].stored, &file).unwrap_err();
+		assert!(err.to_string().contains("exists again"), "{}", err);
+		assert_eq!(fs::read_to_string(&file).unwrap(), "newer");
+		assert!(entries[0].stored.exists());
+
+		fs::remove_file(&file).unwrap();
+		restore_from_trash(&entries[0].stored, &file).unwrap();
+		assert_eq!(fs::read_to_string(&file).unwrap(), "data");
+		assert!(trash_entries().unwrap().is_empty());
+	}
+
+	#[test]
+	fn copy_and_remove_moves_files_and_trees() {
+		let tmp = tempfile::tempdir().unwrap();
+		let (from, to) = (tmp.path().join("from"), tmp.path().join("to"));
+		fs::create_dir_all(from.join("sub/deeper")).unwrap();
+		fs::write(from.join("top.txt"), "t").unwrap();
+		fs::write(from.join("sub/deeper/leaf.txt"), "l").unwrap();
+		copy_and_remove(&from, &to).unwrap();
+		assert!(!from.exists());
+		assert_eq!(fs::read_to_string(to.join("top.txt")).unwrap(), "t");
+		assert_eq!(fs::read_to_string(to.join("sub/deeper/leaf.txt")).unwrap(), "l");
+
+		copy_and_remove(&to.join("top.txt"), &tmp.path().join("moved.txt")).unwrap();
+		assert!(!to.join("top.txt").exists());
+		assert_eq!(fs::read_to_string(tmp.path().join("moved.txt")).unwrap(), "t");
+	}
+
+	#[test]
+	fn diff_preview_shows_the_changed_middle() {
+		assert_eq!(diff_preview("a\nb\nc\n", "a\nB\nc\n"), "@@ line 2 @@\n-b\n+B");
+		assert_eq!(diff_preview("", "x\ny"), "@@ line 1 @@\n+x\n+y");
+		assert_eq!(diff_preview("a\nb", "a"), "@@ line 2 @@\n-b");
+		assert_eq!(diff_preview("same\n", "same\n"), "(no change)");
+		let long: String = (0..50).map(|i| format!("{}\n", i)).collect();
+		let preview = diff_preview("", &long);
+		assert_eq!(preview.lines().count(), MAX_PREVIEW_LINES + 2);
+		assert!(preview.ends_with("... 10 more changed lines"), "{}", preview);
+	}
+
+	/// Every path under `dir` with the content of each file.
+	fn tree(dir: &Path) -> Vec<(PathBuf, Option<String>)> {
+		walkdir::WalkDir::new(dir).sort_by_file_name().into_iter().map(|e| {
+			let e = e.unwrap();
+			(e.path().to_path_buf(), e.file_type().is_file().then(|| fs::read_to_string(e.path()).unwrap()))
+		}).collect()
+	}
+
+	#[tokio::test]
+	async fn plan_mode_changes_nothing() {
+		let (tmp, root, settings) = sandbox();
+		let patterns = [("touch".to_string(), vec!["*".to_string()])].into_iter().collect();
+		let settings = Settings {
+			actions_mode: Some("plan".to_string()),
+			allow_apps: Some(vec!["touch".to_string()]),
+			command_arg_patterns: Some(patterns),
+			..settings
+		};
+		fs::create_dir(root.join("docs")).unwrap();
+		fs::write(root.join("notes.txt"), "old\nkept\n").unwrap();
+		let before = tree(tmp.path());
+		// spelled through "docs/.." to show the plan names the resolved path
+		let notes = root.join("docs/../notes.txt");
+		let notes_shown = root.join("notes.txt").display().to_string();
+
+		let reply = execute(&settings, Action::WriteFile { path: notes.clone(), content: "new\nkept\n".to_string() }).await.unwrap();
+		assert!(reply.starts_with(&format!("Would overwrite: {} (", notes_shown)), "{}", reply);
+		assert!(reply.ends_with("@@ line 1 @@\n-old\n+new"), "{}", reply);
+		let reply = execute(&settings, Action::WriteFile { path: root.join("fresh.txt"), content: "hello".to_string() }).await.unwrap();
+		assert_eq!(reply, format!("Would create: {}\n@@ line 1 @@\n+hello", root.join("fresh.txt").display()));
+		let reply = execute(&settings, Action::AppendFile { path: notes.clone(), content: "more\n".to_string() }).await.unwrap();
+		assert_eq!(reply, format!("Would append 5 bytes to: {}\n@@ line 1 @@\n+more", notes_shown));
+		let reply = execute(&settings, Action::CreateDir(root.join("a/b"))).await.unwrap();
+		assert_eq!(reply, format!("Would create directory: {} (starting at {})", root.join("a/b").display(), root.join("a").display()));
+		let reply = execute(&settings, Action::MoveToTrash(notes.clone())).await.unwrap();
+		assert!(reply.starts_with(&format!("Would move to trash: {}\n{}\nkind: file", notes_shown, notes_shown)), "{}", reply);
+		let run = Action::RunCommand { program: "touch".to_string(), args: vec!["made.txt".to_string()], cwd: Some(root.join("docs/..")) };
+		let reply = execute(&settings, run).await.unwrap();
+		assert!(reply.starts_with(&format!("Would run: touch made.txt\nin: {}\n", root.display())), "{}", reply);
+
+		assert_eq!(tree(tmp.path()), before);
+	}
+}
\ No newline at end of file
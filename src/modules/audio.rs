@@ -0,0 +1,521 @@
+#![cfg(feature = "stt-vosk")]
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use crate::settings::Settings;
+
+// Sources hand out audio in blocks of roughly this length.
+const BLOCK_MS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormat {
+	pub sample_rate: u32,
+	pub channels: u16,
+}
+
+impl AudioFormat {
+	fn block_len(&self) -> usize {
+		(self.sample_rate as usize * BLOCK_MS / 1000).max(1) * self.channels as usize
+	}
+}
+
+/// A pull-based stream of interleaved f32 samples in [-1, 1].
+pub trait AudioSource {
+	fn format(&self) -> AudioFormat;
+	/// Next block of interleaved samples, or `None` once the stream has ended.
+	fn next_block(&mut self) -> Result<Option<Vec<f32>>>;
+}
+
+/// Live capture from a cpal input device.
+pub struct CpalSource {
+	_stream: cpal::Stream,
+	rx: mpsc::Receiver<Vec<f32>>,
+	format: AudioFormat,
+	name: String,
+	lost: Arc<AtomicBool>,
+}
+
+impl CpalSource {
+	/// Open `stt_input_device` if configured, otherwise the host's default input.
+	pub fn from_settings(settings: &Settings) -> Result<Self> {
+		match settings.stt_input_device.as_deref() {
+			Some(spec) => Self::open(&find_input_device(spec)?),
+			None => Self::open_default(),
+		}
+	}
+
+	pub fn open_default() -> Result<Self> {
+		let host = cpal::default_host();
+		let device = host.default_input_device().ok_or_else(|| anyhow!("No default input device"))?;
+		Self::open(&device)
+	}
+
+	pub fn open(device: &cpal::Device) -> Result<Self> {
+		let name = device.name().unwrap_or_else(|_| "<unknown>".to_string());
+		let config = device.default_input_config().map_err(|e| anyhow!("Input device '{}' unusable: {}", name, e))?;
+		let format = AudioFormat { sample_rate: config.sample_rate().0, channels: config.channels() };
+		let (tx, rx) = mpsc::channel();
+		let lost = Arc::new(AtomicBool::new(false));
+		let stream_config: cpal::StreamConfig = config.clone().into();
+		let stream = match config.sample_format() {
+			cpal::SampleFormat::F32 => build_stream::<f32>(device, &stream_config, tx, lost.clone())?,
+			cpal::SampleFormat::I16 => build_stream::<i16>(device, &stream_config, tx, lost.clone())?,
+			cpal::SampleFormat::U16 => build_stream::<u16>(device, &stream_config, tx, lost.clone())?,
+			cpal::SampleFormat::I32 => build_stream::<i32>(device, &stream_config, tx, lost.clone())?,
+			other => return Err(anyhow!("Unsupported sample format: {:?}", other)),
+		};
+		stream.play()?;
+		Ok(Self { _stream: stream, rx, format, name, lost })
+	}
+
+	/// Drop audio captured so far, e.g. while the assistant itself was speaking.
+	#[cfg(feature = "speech")]
+	pub fn discard_buffered(&mut self) {
+		while self.rx.try_recv().is_ok() {}
+	}
+
+	/// A block already captured, or None without waiting.
+	#[cfg(feature = "speech")]
+	pub fn try_next_block(&mut self) -> Result<Option<Vec<f32>>> {
+		if self.lost.load(Ordering::SeqCst) {
+			return Err(anyhow!("Input device '{}' is no longer available (unplugged?)", self.name));
+		}
+		match self.rx.try_recv() {
+			Ok(block) => Ok(Some(block)),
+			Err(mpsc::TryRecvError::Empty) => Ok(None),
+			Err(mpsc::TryRecvError::Disconnected) => Err(anyhow!("Microphone stream ended")),
+		}
+	}
+}
+
+fn build_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, tx: mpsc::Sender<Vec<f32>>, lost: Arc<AtomicBool>) -> Result<cpal::Stream>
+where
+	T: SizedSample,
+	f32: FromSample<T>,
+{
+	let stream = device.build_input_stream(
+		config,
+		move |data: &[T], _| { let _ = tx.send(data.iter().map(|s| s.to_sample::<f32>()).collect()); },
+		move |err| {
+			if matches!(err, cpal::StreamError::DeviceNotAvailable) { lost.store(true, Ordering::SeqCst); }
+			err_fn(err);
+		},
+		None,
+	)?;
+	Ok(stream)
+}
+
+impl AudioSource for CpalSource {
+	fn format(&self) -> AudioFormat { self.format }
+
+	fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+		if self.lost.load(Ordering::SeqCst) {
+			return Err(anyhow!("Input device '{}' is no longer available (unplugged?)", self.name));
+		}
+		match self.rx.recv_timeout(Duration::from_secs(2)) {
+			Ok(block) => Ok(Some(block)),
+			Err(mpsc::RecvTimeoutError::Timeout) => Err(anyhow!("Input device '{}' stopped delivering audio", self.name)),
+			Err(mpsc::RecvTimeoutError::Disconnected) => Ok(None),
+		}
+	}
+}
+
+/// Find an input device on the default host by index (as shown by `milyai audio devices`) or name.
+/// Names match exactly (case-insensitive) first, then by substring.
+pub fn find_input_device(spec: &str) -> Result<cpal::Device> {
+	let host = cpal::default_host();
+	let devices: Vec<(String, cpal::Device)> = host
+		.input_devices()?
+		.map(|d| (d.name().unwrap_or_default(), d))
+		.collect();
+	if let Ok(index) = spec.trim().parse::<usize>() {
+		return devices.into_iter().nth(index).map(|(_, d)| d).ok_or_else(|| anyhow!("No input device with index {} (see `milyai audio devices`)", index));
+	}
+	let wanted = spec.to_lowercase();
+	let pos = devices.iter().position(|(n, _)| n.to_lowercase() == wanted).or_else(|| devices.iter().position(|(n, _)| n.to_lowercase().contains(&wanted)));
+	match pos {
+		Some(i) => Ok(devices.into_iter().nth(i).unwrap().1),
+		None => Err(anyhow!("Input device '{}' not found; available: {}", spec, devices.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(", "))),
+	}
+}
+
+/// Describe every host with its input and output devices and supported stream configs.
+pub fn describe_devices() -> Result<String> {
+	let mut out = String::new();
+	let default_host = cpal::default_host().id();
+	for host_id in cpal::available_hosts() {
+		let host = match cpal::host_from_id(host_id) {
+			Ok(h) => h,
+			Err(e) => { out.push_str(&format!("Host {} (unavailable: {})\n", host_id.name(), e)); continue; }
+		};
+		let marker = if host_id == default_host { " [default]" } else { "" };
+		out.push_str(&format!("Host {}{}\n", host_id.name(), marker));
+		let default_in = host.default_input_device().and_then(|d| d.name().ok());
+		let default_out = host.default_output_device().and_then(|d| d.name().ok());
+		out.push_str("  Input devices:\n");
+		for (i, d) in host.input_devices()?.enumerate() {
+			let name = d.name().unwrap_or_else(|_| "<unknown>".to_string());
+			let marker = if default_in.as_deref() == Some(name.as_str()) { " [default]" } else { "" };
+			out.push_str(&format!("    {}: {}{}\n", i, name, marker));
+			if let Ok(configs) = d.supported_input_configs() {
+				for c in configs { out.push_str(&format!("       {}\n", describe_config(&c))); }
+			}
+		}
+		out.push_str("  Output devices:\n");
+		for (i, d) in host.output_devices()?.enumerate() {
+			let name = d.name().unwrap_or_else(|_| "<unknown>".to_string());
+			let marker = if default_out.as_deref() == Some(name.as_str()) { " [default]" } else { "" };
+			out.push_str(&format!("    {}: {}{}\n", i, name, marker));
+			if let Ok(configs) = d.supported_output_configs() {
+				for c in configs { out.push_str(&format!("       {}\n", describe_config(&c))); }
+			}
+		}
+	}
+	Ok(out)
+}
+
+fn describe_config(c: &cpal::SupportedStreamConfigRange) -> String {
+	format!("{} ch, {}-{} Hz, {:?}", c.channels(), c.min_sample_rate().0, c.max_sample_rate().0, c.sample_format())
+}
+
+/// WAV file (any PCM bit depth or float) via hound.
+pub struct WavSource<R: Read> {
+	reader: hound::WavReader<R>,
+	format: AudioFormat,
+}
+
+impl WavSource<std::io::BufReader<std::fs::File>> {
+	pub fn open(path: &Path) -> Result<Self> {
+		let reader = hound::WavReader::open(path).map_err(|e| anyhow!("Cannot read WAV {}: {}", path.display(), e))?;
+		Self::from_reader(reader)
+	}
+}
+
+impl<R: Read> WavSource<R> {
+	pub fn from_reader(reader: hound::WavReader<R>) -> Result<Self> {
+		let spec = reader.spec();
+		let format = AudioFormat { sample_rate: spec.sample_rate, channels: spec.channels };
+		Ok(Self { reader, format })
+	}
+}
+
+impl<R: Read> AudioSource for WavSource<R> {
+	fn format(&self) -> AudioFormat { self.format }
+
+	fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+		let n = self.format.block_len();
+		let spec = self.reader.spec();
+		let block: Vec<f32> = match spec.sample_format {
+			hound::SampleFormat::Float => self.reader.samples::<f32>().take(n).collect::<Result<_, _>>()?,
+			hound::SampleFormat::Int => {
+				let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+				self.reader.samples::<i32>().take(n).map(|s| s.map(|v| v as f32 / scale)).collect::<Result<_, _>>()?
+			}
+		};
+		Ok(if block.is_empty() { None } else { Some(block) })
+	}
+}
+
+/// Raw signed 16-bit little-endian PCM, e.g. piped from `arecord` or `ffmpeg -f s16le`.
+pub struct PcmSource<R: Read> {
+	reader: R,
+	format: AudioFormat,
+}
+
+impl PcmSource<std::io::Stdin> {
+	pub fn stdin(format: AudioFormat) -> Self {
+		Self::new(std::io::stdin(), format)
+	}
+}
+
+impl<R: Read> PcmSource<R> {
+	pub fn new(reader: R, format: AudioFormat) -> Self {
+		Self { reader, format }
+	}
+}
+
+impl<R: Read> AudioSource for PcmSource<R> {
+	fn format(&self) -> AudioFormat { self.format }
+
+	fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+		let mut buf = vec![0u8; self.format.block_len() * 2];
+		let mut filled = 0;
+		while filled < buf.len() {
+			match self.reader.read(&mut buf[filled..])? {
+				0 => break,
+				n => filled += n,
+			}
+		}
+		if filled < 2 { return Ok(None); }
+		Ok(Some(buf[..filled - filled % 2].chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0).collect()))
+	}
+}
+
+/// Samples already in memory, for tests with fixtures and synthetic signals.
+#[cfg(test)]
+pub struct MemorySource {
+	samples: Vec<f32>,
+	pos: usize,
+	format: AudioFormat,
+}
+
+#[cfg(test)]
+impl MemorySource {
+	pub fn new(samples: Vec<f32>, format: AudioFormat) -> Self {
+		Self { samples, pos: 0, format }
+	}
+}
+
+#[cfg(test)]
+impl AudioSource for MemorySource {
+	fn format(&self) -> AudioFormat { self.format }
+
+	fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+		if self.pos >= self.samples.len() { return Ok(None); }
+		let end = (self.pos + self.format.block_len()).min(self.samples.len());
+		let block = self.samples[self.pos..end].to_vec();
+		self.pos = end;
+		Ok(Some(block))
+	}
+}
+
+fn err_fn(err: cpal::StreamError) {
+	eprintln!("Audio stream error: {}", err);
+}
+
+/// Sample rate most Vosk models are trained on.
+pub const MODEL_SAMPLE_RATE: u32 = 16_000;
+
+// Windowed-sinc half width in zero crossings; 16 keeps aliasing well below speech levels.
+const SINC_ZERO_CROSSINGS: f64 = 16.0;
+
+/// Streaming conversion of interleaved device audio into mono i16 at the recognizer's rate:
+/// channel downmix, band-limited (windowed-sinc) resampling, then quantization.
+pub struct Converter {
+	channels: usize,
+	step: f64,
+	cutoff: f64,
+	half_width: f64,
+	passthrough: bool,
+	buf: Vec<f32>,
+	/// Input samples already dropped from the front of `buf`
+	dropped: u64,
+	/// Output samples produced so far; the next is read at `emitted * step` in input samples, counted
+	/// from the start so rounding cannot drift with the block size
+	emitted: u64,
+}
+
+impl Converter {
+	pub fn new(input: AudioFormat, out_rate: u32) -> Self {
+		let ratio = out_rate as f64 / input.sample_rate as f64;
+		// low-pass at the output Nyquist when downsampling
+		let cutoff = ratio.min(1.0);
+		Self {
+			channels: input.channels.max(1) as usize,
+			step: 1.0 / ratio,
+			cutoff,
+			half_width: SINC_ZERO_CROSSINGS / cutoff,
+			passthrough: input.sample_rate == out_rate,
+			buf: Vec::new(),
+			dropped: 0,
+			emitted: 0,
+		}
+	}
+
+	/// Convert one block; output may lag input by the filter half-width until `flush`.
+	pub fn process(&mut self, interleaved: &[f32]) -> Vec<i16> {
+		let mono = downmix(interleaved, self.channels);
+		if self.passthrough { return mono.iter().map(|s| to_i16(*s)).collect(); }
+		self.buf.extend(mono);
+		self.drain(false)
+	}
+
+	/// Emit the samples still held back by the filter window.
+	pub fn flush(&mut self) -> Vec<i16> {
+		if self.passthrough { return Vec::new(); }
+		self.drain(true)
+	}
+
+	fn drain(&mut self, at_end: bool) -> Vec<i16> {
+		let mut out = Vec::new();
+		let end = (self.dropped + self.buf.len() as u64) as f64;
+		loop {
+			let t = self.emitted as f64 * self.step;
+			if !((at_end && t < end) || t + self.half_width < end) { break; }
+			out.push(to_i16(self.sample_at(t)));
+			self.emitted += 1;
+		}
+		// keep only the history the next window still needs
+		let next = self.emitted as f64 * self.step;
+		let keep_from = ((next - self.half_width).floor().max(0.0) as u64).saturating_sub(self.dropped).min(self.buf.len() as u64);
+		self.buf.drain(..keep_from as usize);
+		self.dropped += keep_from;
+		out
+	}
+
+	/// Filtered input at stream position `t`.
+	fn sample_at(&self, t: f64) -> f32 {
+		let first = ((t - self.half_width).ceil().max(0.0) as u64).max(self.dropped);
+		let last = ((t + self.half_width).floor() as u64).min((self.dropped + self.buf.len() as u64).saturating_sub(1));
+		let mut acc = 0.0f64;
+		for k in first..=last {
+			let x = t - k as f64;
+			let k = (k - self.dropped) as usize;
+			acc += self.buf[k] as f64 * self.cutoff * sinc(self.cutoff * x) * blackman(x / self.half_width);
+		}
+		acc as f32
+	}
+}
+
+pub fn downmix(interleaved: &[f32], channels: usize) -> Vec<f32> {
+	if channels <= 1 { return interleaved.to_vec(); }
+	interleaved.chunks_exact(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32).collect()
+}
+
+fn to_i16(s: f32) -> i16 {
+	(s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+fn sinc(x: f64) -> f64 {
+	if x.abs() < 1e-9 { 1.0 } else { (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x) }
+}
+
+/// Blackman window over [-1, 1].
+fn blackman(x: f64) -> f64 {
+	if x.abs() > 1.0 { return 0.0; }
+	let a = std::f64::consts::PI * (x + 1.0);
+	0.42 - 0.5 * a.cos() + 0.08 * (2.0 * a).cos()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const WAV_FORMAT: AudioFormat = AudioFormat { sample_rate: 44_100, channels: 2 };
+
+	/// Half a second of a 440 Hz tone at half scale, the same on both channels, as 16-bit steps.
+	fn stereo_tone() -> Vec<i16> {
+		(0..WAV_FORMAT.sample_rate / 2)
+			.flat_map(|i| {
+				let v = (0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / WAV_FORMAT.sample_rate as f32).sin() * 32768.0) as i16;
+				[v, v]
+			})
+			.collect()
+	}
+
+	fn drain(source: &mut dyn AudioSource) -> Vec<Vec<f32>> {
+		std::iter::from_fn(|| source.next_block().unwrap()).collect()
+	}
+
+	fn rms_i16(samples: &[i16]) -> f32 {
+		(samples.iter().map(|s| (*s as f32 / 32768.0).powi(2)).sum::<f32>() / samples.len() as f32).sqrt()
+	}
+
+	fn sine(freq: f32, rate: u32, secs: f32) -> Vec<f32> {
+		(0..(rate as f32 * secs) as usize).map(|i| 0.5 * (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin()).collect()
+	}
+
+	fn convert_all(converter: &mut Converter, input: &[f32], block: usize) -> Vec<i16> {
+		let mut out: Vec<i16> = input.chunks(block).flat_map(|b| converter.process(b)).collect();
+		out.extend(converter.flush());
+		out
+	}
+
+	#[test]
+	fn converter_passes_model_rate_mono_through() {
+		let input = sine(440.0, MODEL_SAMPLE_RATE, 0.1);
+		let mut converter = Converter::new(AudioFormat { sample_rate: MODEL_SAMPLE_RATE, channels: 1 }, MODEL_SAMPLE_RATE);
+		let out = convert_all(&mut converter, &input, 160);
+		assert_eq!(out, input.iter().map(|s| to_i16(*s)).collect::<Vec<_>>());
+		assert_eq!(to_i16(1.5), i16::MAX);
+		assert_eq!(to_i16(-1.5), -i16::MAX);
+	}
+
+	#[test]
+	fn converter_downmixes_channels() {
+		let mut converter = Converter::new(AudioFormat { sample_rate: MODEL_SAMPLE_RATE, channels: 2 }, MODEL_SAMPLE_RATE);
+		assert_eq!(converter.process(&[0.5, -0.5, 0.25, 0.25, 1.0, 0.0]), vec![0, to_i16(0.25), to_i16(0.5)]);
+		assert_eq!(downmix(&[0.1, 0.2, 0.3], 1), vec![0.1, 0.2, 0.3]);
+	}
+
+	#[test]
+	fn converter_resamples_accurately() {
+		let input = sine(1000.0, 48_000, 0.5);
+		let mut converter = Converter::new(AudioFormat { sample_rate: 48_000, channels: 1 }, MODEL_SAMPLE_RATE);
+		let out = convert_all(&mut converter, &input, 4800);
+		assert_eq!(out.len(), 8000);
+		let expected = sine(1000.0, MODEL_SAMPLE_RATE, 0.5);
+		// away from the edges, where the filter window runs off the signal
+		let worst = (100..7900).map(|i| (out[i] as f32 / 32767.0 - expected[i]).abs()).fold(0.0f32, f32::max);
+		assert!(worst < 0.01, "{}", worst);
+	}
+
+	#[test]
+	fn converter_filters_above_output_nyquist() {
+		// 12 kHz cannot be represented at 16 kHz and must not fold back down to 4 kHz
+		let input = sine(12_000.0, 48_000, 0.5);
+		let mut converter = Converter::new(AudioFormat { sample_rate: 48_000, channels: 1 }, MODEL_SAMPLE_RATE);
+		let out = convert_all(&mut converter, &input, 4800);
+		assert!(rms_i16(&out[100..7900]) < 0.01, "{}", rms_i16(&out[100..7900]));
+	}
+
+	#[test]
+	fn converter_output_does_not_depend_on_block_size() {
+		let input = sine(300.0, 44_100, 0.3);
+		let format = AudioFormat { sample_rate: 44_100, channels: 1 };
+		let whole = convert_all(&mut Converter::new(format, MODEL_SAMPLE_RATE), &input, input.len());
+		let pieces = convert_all(&mut Converter::new(format, MODEL_SAMPLE_RATE), &input, 97);
+		assert_eq!(whole, pieces);
+		let up = convert_all(&mut Converter::new(AudioFormat { sample_rate: 8000, channels: 1 }, MODEL_SAMPLE_RATE), &sine(300.0, 8000, 0.3), 80);
+		assert_eq!(up.len(), 4800);
+	}
+
+	#[test]
+	fn wav_memory_and_pcm_sources_agree() {
+		let pcm = stereo_tone();
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("tone.wav");
+		let spec = hound::WavSpec { channels: 2, sample_rate: WAV_FORMAT.sample_rate, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+		let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+		for s in &pcm { writer.write_sample(*s).unwrap(); }
+		writer.finalize().unwrap();
+
+		let mut wav = WavSource::open(&path).unwrap();
+		assert_eq!(wav.format(), WAV_FORMAT);
+		let from_wav = drain(&mut wav);
+		// 100 ms blocks of interleaved frames
+		assert_eq!(from_wav.len(), 5);
+		assert!(from_wav.iter().all(|b| b.len() == 4410 * 2));
+
+		let samples: Vec<f32> = pcm.iter().map(|s| *s as f32 / 32768.0).collect();
+		assert_eq!(drain(&mut MemorySource::new(samples, WAV_FORMAT)), from_wav);
+		let bytes: Vec<u8> = pcm.iter().flat_map(|s| s.to_le_bytes()).collect();
+		assert_eq!(drain(&mut PcmSource::new(std::io::Cursor::new(bytes), WAV_FORMAT)), from_wav);
+	}
+
+	#[test]
+	fn wav_converts_to_model_rate_mono() {
+		let pcm = stereo_tone();
+		let mut cursor = std::io::Cursor::new(Vec::new());
+		let spec = hound::WavSpec { channels: 2, sample_rate: WAV_FORMAT.sample_rate, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+		let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+		for s in &pcm { writer.write_sample(*s).unwrap(); }
+		writer.finalize().unwrap();
+		cursor.set_position(0);
+
+		let mut source = WavSource::from_reader(hound::WavReader::new(cursor).unwrap()).unwrap();
+		let mut converter = Converter::new(source.format(), MODEL_SAMPLE_RATE);
+		let mut out = Vec::new();
+		while let Some(block) = source.next_block().unwrap() { out.extend(converter.process(&block)); }
+		out.extend(converter.flush());
+		// half a second at 16 kHz, and the tone's level (0.5 / sqrt 2) survives
+		assert!(out.len().abs_diff(MODEL_SAMPLE_RATE as usize / 2) <= 1, "{}", out.len());
+		assert!((rms_i16(&out[500..7500]) - 0.3536).abs() < 0.01, "{}", rms_i16(&out[500..7500]));
+	}
+}
\ No newline at end of file
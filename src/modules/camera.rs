@@ -1,14 +1,150 @@
 #![cfg(feature = "camera")]
-use anyhow::Result;
-use nokhwa::{pixel_format::RgbFormat, Camera};
+use anyhow::{anyhow, Result};
+use image::RgbImage;
+use nokhwa::pixel_format::{FormatDecoder, RgbFormat};
+use nokhwa::utils::{ApiBackend, CameraFormat, CameraIndex, CameraInfo, RequestedFormat, RequestedFormatType};
+use nokhwa::Camera;
+use std::cmp::Reverse;
 
-pub fn snapshot(output: &str) -> Result<()> {
-	let mut cam = Camera::new(0, None)?;
+use crate::settings::Settings;
+
+/// Frames dropped after opening; the first ones are often dark while auto-exposure adjusts
+const DEFAULT_WARMUP_FRAMES: u32 = 5;
+
+/// Cameras with their index for `camera_device` and every capture format they support.
+pub fn describe_devices() -> Result<String> {
+	let cameras = nokhwa::query(ApiBackend::Auto)?;
+	if cameras.is_empty() { return Ok("No cameras found\n".to_string()); }
+	let mut out = String::new();
+	for (i, info) in cameras.iter().enumerate() {
+		let marker = if i == 0 { " [default]" } else { "" };
+		out.push_str(&format!("{}: {}{}\n", i, info.human_name(), marker));
+		if !info.description().is_empty() { out.push_str(&format!("   {}\n", info.description())); }
+		let formats = Camera::new(info.index().clone(), RequestedFormat::new::<RgbFormat>(RequestedFormatType::None))
+			.and_then(|mut cam| cam.compatible_camera_formats());
+		match formats {
+			Ok(mut formats) => {
+				formats.sort_by_key(|f| (f.format().to_string(), Reverse(f.resolution()), Reverse(f.frame_rate())));
+				for f in formats { out.push_str(&format!("     {} {}x{} @ {} fps\n", f.format(), f.width(), f.height(), f.frame_rate())); }
+			}
+			Err(e) => out.push_str(&format!("     (formats unavailable: {})\n", e)),
+		}
+	}
+	Ok(out)
+}
+
+/// Find a camera by index (as shown by `milyai camera list`) or name.
+/// Names match exactly (case-insensitive) first, then by substring.
+pub fn find_camera(spec: &str) -> Result<CameraIndex> {
+	let cameras: Vec<CameraInfo> = nokhwa::query(ApiBackend::Auto)?;
+	if let Ok(index) = spec.trim().parse::<usize>() {
+		return cameras.get(index).map(|c| c.index().clone()).ok_or_else(|| anyhow!("No camera with index {} (see `milyai camera list`)", index));
+	}
+	let wanted = spec.to_lowercase();
+	let found = cameras.iter().find(|c| c.human_name().to_lowercase() == wanted).or_else(|| cameras.iter().find(|c| c.human_name().to_lowercase().contains(&wanted)));
+	match found {
+		Some(c) => Ok(c.index().clone()),
+		None => Err(anyhow!("Camera '{}' not found; available: {}", spec, cameras.iter().map(|c| c.human_name()).collect::<Vec<_>>().join(", "))),
+	}
+}
+
+/// Parse "1280x720" into (width, height).
+pub fn parse_resolution(s: &str) -> Result<(u32, u32)> {
+	let (w, h) = s.trim().split_once(['x', 'X', '×']).ok_or_else(|| anyhow!("camera_resolution must look like 1280x720, got '{}'", s))?;
+	Ok((w.trim().parse()?, h.trim().parse()?))
+}
+
+/// The decodable format closest to the wanted resolution, then frame rate. Unset preferences favour
+/// the largest resolution and highest frame rate.
+pub fn choose_format(formats: &[CameraFormat], resolution: Option<(u32, u32)>, fps: Option<u32>) -> Option<CameraFormat> {
+	formats
+		.iter()
+		.filter(|f| RgbFormat::FORMATS.contains(&f.format()))
+		.min_by_key(|f| {
+			let res_dist = resolution.map_or(0, |(w, h)| f.width().abs_diff(w) + f.height().abs_diff(h));
+			let fps_dist = fps.map_or(0, |r| f.frame_rate().abs_diff(r));
+			(res_dist, fps_dist, Reverse(f.width() * f.height()), Reverse(f.frame_rate()))
+		})
+		.copied()
+}
+
+/// Open the configured camera in the configured format and let it warm up.
+pub fn open(settings: &Settings) -> Result<Camera> {
+	let index = match settings.camera_device.as_deref() {
+		Some(spec) => find_camera(spec)?,
+		None => nokhwa::query(ApiBackend::Auto)?.first().map(|c| c.index().clone()).ok_or_else(|| anyhow!("No cameras found"))?,
+	};
+	let mut cam = Camera::new(index, RequestedFormat::new::<RgbFormat>(RequestedFormatType::None))?;
+	let resolution = settings.camera_resolution.as_deref().map(parse_resolution).transpose()?;
+	if resolution.is_some() || settings.camera_fps.is_some() {
+		let formats = cam.compatible_camera_formats()?;
+		let format = choose_format(&formats, resolution, settings.camera_fps).ok_or_else(|| anyhow!("Camera reports no usable capture format"))?;
+		cam.set_camera_requset(RequestedFormat::new::<RgbFormat>(RequestedFormatType::Exact(format)))?;
+	}
 	cam.open_stream()?;
-	let frame = cam.frame()?;
-	let decoded = frame.decode_image::<RgbFormat>()?;
-	let (w, h) = (decoded.width(), decoded.height());
-	let buffer = decoded.buffer();
-	image::save_buffer(output, buffer, w, h, image::ExtendedColorType::Rgb8)?;
+	for _ in 0..settings.camera_warmup_frames.unwrap_or(DEFAULT_WARMUP_FRAMES) {
+		cam.frame()?;
+	}
+	Ok(cam)
+}
+
+pub fn capture(cam: &mut Camera) -> Result<RgbImage> {
+	Ok(cam.frame()?.decode_image::<RgbFormat>()?)
+}
+
+pub fn snapshot(settings: &Settings, output: &str) -> Result<()> {
+	let mut cam = open(settings)?;
+	let frame = capture(&mut cam)?;
+	frame.save(output)?;
 	Ok(())
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nokhwa::utils::{FrameFormat, Resolution};
+
+	fn format(kind: FrameFormat, w: u32, h: u32, fps: u32) -> CameraFormat {
+		CameraFormat::new(Resolution::new(w, h), kind, fps)
+	}
+
+	#[test]
+	fn parse_resolution_accepts_x_and_times_sign() {
+		assert_eq!(parse_resolution("1280x720").unwrap(), (1280, 720));
+		assert_eq!(parse_resolution(" 640 × 480 ").unwrap(), (640, 480));
+		assert_eq!(parse_resolution("1920X1080").unwrap(), (1920, 1080));
+		for bad in ["1280", "axb", "1280x", ""] {
+			assert!(parse_resolution(bad).is_err(), "{}", bad);
+		}
+	}
+
+	#[test]
+	fn choose_format_prefers_resolution_then_frame_rate() {
+		let formats = [
+			format(FrameFormat::MJPEG, 640, 480, 30),
+			format(FrameFormat::MJPEG, 1280, 720, 30),
+			format(FrameFormat::YUYV, 1280, 720, 10),
+			format(FrameFormat::MJPEG, 1920, 1080, 15),
+		];
+		assert_eq!(choose_format(&formats, Some((1280, 720)), None), Some(formats[1]));
+		assert_eq!(choose_format(&formats, Some((1280, 720)), Some(10)), Some(formats[2]));
+		assert_eq!(choose_format(&formats, Some((1300, 700)), Some(12)), Some(formats[2]));
+		assert_eq!(choose_format(&formats, Some((320, 240)), Some(60)), Some(formats[0]));
+		// with only a frame rate, the largest resolution at that rate wins
+		assert_eq!(choose_format(&formats, None, Some(30)), Some(formats[1]));
+	}
+
+	#[test]
+	fn choose_format_falls_back_to_the_largest_decodable_format() {
+		let formats = [
+			format(FrameFormat::MJPEG, 640, 480, 30),
+			format(FrameFormat::MJPEG, 1920, 1080, 15),
+			format(FrameFormat::YUYV, 1920, 1080, 5),
+		];
+		assert_eq!(choose_format(&formats, None, None), Some(formats[1]));
+		assert_eq!(choose_format(&[], Some((1280, 720)), Some(30)), None);
+		let any = [FrameFormat::MJPEG, FrameFormat::YUYV, FrameFormat::NV12, FrameFormat::GRAY, FrameFormat::RAWRGB, FrameFormat::RAWBGR];
+		let all: Vec<_> = any.iter().map(|&k| format(k, 640, 480, 30)).collect();
+		assert!(choose_format(&all, None, None).is_some_and(|f| RgbFormat::FORMATS.contains(&f.format())));
+	}
+}
\ No newline at end of file
@@ -0,0 +1,206 @@
+#![cfg(feature = "actions")]
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::settings::Settings;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Per stream; the rest is read and dropped so the command never blocks on a full pipe
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+/// How long output is still collected after the program ends or is killed
+const READER_GRACE: Duration = Duration::from_secs(2);
+/// Kept from our environment; everything else is cleared
+const PASSTHROUGH_ENV: &[&str] = &["PATH", "HOME", "USERPROFILE", "LANG", "SYSTEMROOT", "TEMP", "TMP"];
+
+/// Arguments are allowed when there are none, or when they match one of the program's
+/// `command_arg_patterns` (e.g. "status", "log *"). Each space-separated word of a pattern is a glob
+/// for one argument, and a final lone `*` stands for one or more further arguments. Options (words
+/// starting with '-') are only accepted where the pattern spells out an option too, so "log *"
+/// does not admit `--output=file`.
+pub fn check_args(settings: &Settings, program: &str, args: &[String]) -> Result<()> {
+	if args.is_empty() { return Ok(()); }
+	let patterns = settings
+		.command_arg_patterns
+		.as_ref()
+		.and_then(|m| m.iter().find(|(app, _)| app.eq_ignore_ascii_case(program)).map(|(_, p)| p))
+		.ok_or_else(|| anyhow!("No command_arg_patterns for {}; it may only run without arguments", program))?;
+	for pattern in patterns {
+		if args_match(pattern, args).map_err(|e| anyhow!("Bad command_arg_patterns entry '{}': {}", pattern, e))? {
+			return Ok(());
+		}
+	}
+	Err(anyhow!("Arguments '{}' match no command_arg_patterns entry for {}", args.join(" "), program))
+}
+
+fn args_match(pattern: &str, args: &[String]) -> Result<bool> {
+	let words: Vec<&str> = pattern.split_whitespace().collect();
+	let (fixed, rest) = match words.split_last() {
+		Some((&"*", fixed)) => (fixed, true),
+		_ => (&words[..], false),
+	};
+	let count_ok = if rest { args.len() > fixed.len() } else { args.len() == fixed.len() };
+	if !count_ok { return Ok(false); }
+	for (word, arg) in fixed.iter().zip(args) {
+		if is_option(arg) && !is_option(word) { return Ok(false); }
+		if !glob::Pattern::new(word)?.matches(arg) { return Ok(false); }
+	}
+	Ok(!args[fixed.len()..].iter().any(|a| is_option(a)))
+}
+
+fn is_option(arg: &str) -> bool {
+	arg.len() > 1 && arg.starts_with('-')
+}
+
+/// Run `program` in `cwd` with a cleared environment and no stdin, killing it after
+/// `command_timeout_secs`, and report its exit status and (capped) output.
+pub fn run(settings: &Settings, program: &str, args: &[String], cwd: &Path) -> Result<String> {
+	let timeout = timeout(settings);
+	let cap = settings.command_max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES);
+	let mut cmd = Command::new(program);
+	cmd.args(args).current_dir(cwd).env_clear().stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+	for key in PASSTHROUGH_ENV {
+		if let Some(v) = std::env::var_os(key) { cmd.env(key, v); }
+	}
+	// its own process group, so a timeout also reaches whatever it started
+	#[cfg(unix)]
+	std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+	let mut child = cmd.spawn().map_err(|e| anyhow!("Failed to start {}: {}", program, e))?;
+	let stdout = capture(child.stdout.take(), cap);
+	let stderr = capture(child.stderr.take(), cap);
+	let started = Instant::now();
+	let status = loop {
+		if let Some(status) = child.try_wait()? { break Some(status); }
+		if started.elapsed() >= timeout {
+			kill(&mut child);
+			let _ = child.wait();
+			break None;
+		}
+		thread::sleep(Duration::from_millis(20));
+	};
+	// something it left running in the background may hold the pipes open; do not wait on it
+	let deadline = Instant::now() + READER_GRACE;
+	while !(stdout.lock().done && stderr.lock().done) && Instant::now() < deadline {
+		thread::sleep(Duration::from_millis(20));
+	}
+	let unfinished = !(stdout.lock().done && stderr.lock().done);
+	let (stdout, stdout_cut) = stdout.lock().take();
+	let (stderr, stderr_cut) = stderr.lock().take();
+
+	let mut out = format!("$ {}\n(in {})\n", command_line(program, args), cwd.display());
+	match status {
+		Some(s) => out.push_str(&format!("exit status: {}\n", s.code().map_or("killed by signal".to_string(), |c| c.to_string()))),
+		None => out.push_str(&format!("killed after the {} s timeout\n", timeout.as_secs())),
+	}
+	for (name, bytes, cut) in [("stdout", stdout, stdout_cut), ("stderr", stderr, stderr_cut)] {
+		if bytes.is_empty() { continue; }
+		out.push_str(&format!("--- {} ---\n{}", name, String::from_utf8_lossy(&bytes)));
+		if !out.ends_with('\n') { out.push('\n'); }
+		if cut { out.push_str(&format!("[truncated at {} bytes]\n", cap)); }
+	}
+	if unfinished { out.push_str("[output may be incomplete: a background process kept it open]\n"); }
+	Ok(out.trim_end().to_string())
+}
+
+pub fn timeout(settings: &Settings) -> Duration {
+	Duration::from_secs(settings.command_timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+}
+
+pub fn command_line(program: &str, args: &[String]) -> String {
+	std::iter::once(program).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ")
+}
+
+/// Kill the program and, on Unix, the rest of its process group.
+fn kill(child: &mut Child) {
+	#[cfg(unix)]
+	// SAFETY: kill(2) has no memory effects; the group id is the child's pid, set at spawn
+	unsafe {
+		libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+	}
+	let _ = child.kill();
+}
+
+#[derive(Default)]
+struct Captured {
+	bytes: Vec<u8>,
+	cut: bool,
+	done: bool,
+}
+
+impl Captured {
+	fn take(&mut self) -> (Vec<u8>, bool) {
+		(std::mem::take(&mut self.bytes), self.cut)
+	}
+}
+
+/// Read a pipe to the end on its own thread, keeping the first `cap` bytes.
+fn capture<R: Read + Send + 'static>(pipe: Option<R>, cap: usize) -> Arc<Mutex<Captured>> {
+	let captured = Arc::new(Mutex::new(Captured::default()));
+	let shared = captured.clone();
+	thread::spawn(move || {
+		if let Some(mut pipe) = pipe {
+			let mut buf = [0u8; 8192];
+			while let Ok(n) = pipe.read(&mut buf) {
+				if n == 0 { break; }
+				let mut c = shared.lock();
+				let room = cap.saturating_sub(c.bytes.len());
+				c.bytes.extend_from_slice(&buf[..n.min(room)]);
+				c.cut |= n > room;
+			}
+		}
+		shared.lock().done = true;
+	});
+	captured
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	fn settings(patterns: &[&str]) -> Settings {
+		let patterns = HashMap::from([("git".to_string(), patterns.iter().map(|p| p.to_string()).collect())]);
+		Settings { command_arg_patterns: Some(patterns), command_timeout_secs: Some(1), ..Default::default() }
+	}
+
+	fn args(line: &str) -> Vec<String> {
+		line.split_whitespace().map(String::from).collect()
+	}
+
+	#[test]
+	fn patterns_match_per_argument() {
+		let s = settings(&["status", "log *", "log --oneline -n *", "diff --stat=*"]);
+		for ok in ["status", "log main", "log main origin/main", "log --oneline -n 5", "diff --stat=80"] {
+			assert!(check_args(&s, "git", &args(ok)).is_ok(), "{}", ok);
+		}
+		for bad in ["status --short", "log", "log --output=/tmp/x", "log main --output=x", "log --oneline -n 5 --all", "diff --cached", "push"] {
+			assert!(check_args(&s, "git", &args(bad)).is_err(), "{}", bad);
+		}
+		// one argument with a space is still one argument
+		assert!(check_args(&s, "git", &["status x".to_string()]).is_err());
+	}
+
+	#[test]
+	fn no_patterns_allows_bare_program_only() {
+		let s = Settings::default();
+		assert!(check_args(&s, "git", &[]).is_ok());
+		assert!(check_args(&s, "git", &args("status")).is_err());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn timeout_kills_background_children() {
+		let started = Instant::now();
+		// the background sleep inherits the pipes and would keep them open for 30 s
+		let script = vec!["-c".to_string(), "sleep 30 & echo started; sleep 30".to_string()];
+		let out = run(&settings(&[]), "sh", &script, Path::new(".")).unwrap();
+		assert!(started.elapsed() < Duration::from_secs(10), "took {:?}", started.elapsed());
+		assert!(out.contains("killed after the 1 s timeout"), "{}", out);
+		assert!(out.contains("started"), "{}", out);
+	}
+}
\ No newline at end of file
@@ -0,0 +1,252 @@
+#![cfg(feature = "web")]
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocKind {
+	Html,
+	Pdf,
+	Markdown,
+	Text,
+	Json,
+}
+
+impl DocKind {
+	/// Resolve the document kind from the content type, then the file extension, then the bytes themselves.
+	pub fn detect(content_type: &str, name: &str, body: &[u8]) -> Result<Self> {
+		let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+		match mime.as_str() {
+			"text/html" | "application/xhtml+xml" => return Ok(Self::Html),
+			"application/pdf" | "application/x-pdf" => return Ok(Self::Pdf),
+			"text/markdown" | "text/x-markdown" => return Ok(Self::Markdown),
+			"application/json" => return Ok(Self::Json),
+			m if m.ends_with("+json") => return Ok(Self::Json),
+			"" | "application/octet-stream" | "binary/octet-stream" => {}
+			"text/plain" => {
+				// servers often label markdown as plain text
+				if let Some(kind) = Self::from_extension(name) { return Ok(kind); }
+				return Ok(Self::Text);
+			}
+			m if m.starts_with("text/") => return Ok(Self::Text),
+			m => return Err(anyhow!("Unsupported content type: {}", m)),
+		}
+		if let Some(kind) = Self::from_extension(name) { return Ok(kind); }
+		Self::sniff(body).ok_or_else(|| anyhow!("Unsupported content: could not detect document type of {}", name))
+	}
+
+	pub fn from_extension(name: &str) -> Option<Self> {
+		let path = name.split(['?', '#']).next().unwrap_or(name);
+		let ext = path.rsplit('/').next().and_then(|f| f.rsplit_once('.')).map(|(_, e)| e.to_lowercase())?;
+		match ext.as_str() {
+			"html" | "htm" | "xhtml" => Some(Self::Html),
+			"pdf" => Some(Self::Pdf),
+			"md" | "markdown" | "mdown" => Some(Self::Markdown),
+			"json" => Some(Self::Json),
+			"txt" | "text" | "log" | "csv" | "rst" => Some(Self::Text),
+			_ => None,
+		}
+	}
+
+	fn sniff(body: &[u8]) -> Option<Self> {
+		if body.starts_with(b"%PDF-") { return Some(Self::Pdf); }
+		let head = String::from_utf8_lossy(&body[..body.len().min(512)]).to_lowercase();
+		let trimmed = head.trim_start_matches('\u{feff}').trim_start();
+		if trimmed.starts_with("<!doctype html") || trimmed.contains("<html") { return Some(Self::Html); }
+		if (trimmed.starts_with('{') || trimmed.starts_with('[')) && serde_json::from_slice::<serde_json::Value>(body).is_ok() {
+			return Some(Self::Json);
+		}
+		// anything without NUL bytes is treated as text
+		if !body[..body.len().min(4096)].contains(&0) { return Some(Self::Text); }
+		None
+	}
+}
+
+/// Extract readable text from a document body. `name` is a URL or path used as a type hint.
+pub fn extract(content_type: &str, name: &str, body: &[u8]) -> Result<String> {
+	let kind = DocKind::detect(content_type, name, body)?;
+	match kind {
+		DocKind::Html => Ok(extract_text_from_html(&decode_text(content_type, body))),
+		DocKind::Pdf => extract_text_from_pdf(body),
+		DocKind::Markdown => Ok(render_markdown(&decode_text(content_type, body))),
+		DocKind::Text => Ok(decode_text(content_type, body)),
+		DocKind::Json => {
+			let value: serde_json::Value = serde_json::from_slice(decode_text(content_type, body).as_bytes())?;
+			Ok(flatten_json(&value))
+		}
+	}
+}
+
+/// Decode bytes using the declared charset, a BOM, or a detected encoding, in that order.
+pub fn decode_text(content_type: &str, body: &[u8]) -> String {
+	let declared = content_type
+		.split(';')
+		.skip(1)
+		.filter_map(|p| p.trim().strip_prefix("charset="))
+		.next()
+		.and_then(|c| encoding_rs::Encoding::for_label(c.trim_matches('"').as_bytes()));
+	let encoding = declared
+		.or_else(|| encoding_rs::Encoding::for_bom(body).map(|(e, _)| e))
+		.unwrap_or_else(|| {
+			let mut det = chardetng::EncodingDetector::new();
+			det.feed(body, true);
+			det.guess(None, true)
+		});
+	let (text, _, _) = encoding.decode(body);
+	text.into_owned()
+}
+
+pub fn extract_text_from_html(html: &str) -> String {
+	let doc = Html::parse_document(html);
+	let body_sel = Selector::parse("body").unwrap();
+	let mut text = String::new();
+	if let Some(body) = doc.select(&body_sel).next() {
+		for t in body.text() {
+			let s = t.trim();
+			if !s.is_empty() { text.push_str(s); text.push('\n'); }
+		}
+	}
+	text
+}
+
+#[cfg(feature = "pdf")]
+fn extract_text_from_pdf(body: &[u8]) -> Result<String> {
+	let text = pdf_extract::extract_text_from_mem(body).map_err(|e| anyhow!("PDF extraction failed: {}", e))?;
+	let mut out = String::new();
+	for line in text.lines() {
+		let s = line.trim();
+		if !s.is_empty() { out.push_str(s); out.push('\n'); }
+	}
+	if out.is_empty() { return Err(anyhow!("PDF contains no extractable text (scanned document?)")); }
+	Ok(out)
+}
+
+#[cfg(not(feature = "pdf"))]
+fn extract_text_from_pdf(_body: &[u8]) -> Result<String> {
+	Err(anyhow!("PDF documents require --features pdf"))
+}
+
+/// Render Markdown to plain text, keeping headings, list bullets and code blocks on their own lines.
+pub fn render_markdown(md: &str) -> String {
+	use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+	let mut out = String::new();
+	let mut lists: Vec<Option<u64>> = Vec::new();
+	for event in Parser::new(md) {
+		match event {
+			Event::Start(Tag::Heading { .. }) | Event::Start(Tag::Paragraph) | Event::Start(Tag::CodeBlock(_)) if lists.is_empty() => {
+				ensure_blank_line(&mut out);
+			}
+			Event::End(TagEnd::Heading(_)) | Event::End(TagEnd::Paragraph) | Event::End(TagEnd::CodeBlock) => {
+				ensure_newline(&mut out);
+			}
+			Event::Start(Tag::List(start)) => {
+				if lists.is_empty() { ensure_blank_line(&mut out); }
+				lists.push(start);
+			}
+			Event::End(TagEnd::List(_)) => { lists.pop(); }
+			Event::Start(Tag::Item) => {
+				ensure_newline(&mut out);
+				out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+				match lists.last_mut() {
+					Some(Some(n)) => { out.push_str(&format!("{}. ", n)); *n += 1; }
+					_ => out.push_str("- "),
+				}
+			}
+			Event::Text(t) | Event::Code(t) => out.push_str(&t),
+			Event::SoftBreak | Event::HardBreak => out.push('\n'),
+			Event::Rule => { ensure_blank_line(&mut out); }
+			_ => {}
+		}
+	}
+	out.trim().to_string() + "\n"
+}
+
+fn ensure_newline(out: &mut String) {
+	if !out.is_empty() && !out.ends_with('\n') { out.push('\n'); }
+}
+
+fn ensure_blank_line(out: &mut String) {
+	ensure_newline(out);
+	if !out.is_empty() && !out.ends_with("\n\n") { out.push('\n'); }
+}
+
+/// Flatten JSON into `path.to[0].key: value` lines, which summarize better than nested braces.
+pub fn flatten_json(value: &serde_json::Value) -> String {
+	let mut out = String::new();
+	flatten_into(value, String::new(), &mut out);
+	out
+}
+
+fn flatten_into(value: &serde_json::Value, path: String, out: &mut String) {
+	use serde_json::Value;
+	match value {
+		Value::Object(map) => {
+			for (k, v) in map {
+				let p = if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+				flatten_into(v, p, out);
+			}
+		}
+		Value::Array(items) => {
+			for (i, v) in items.iter().enumerate() {
+				flatten_into(v, format!("{}[{}]", path, i), out);
+			}
+		}
+		scalar => {
+			let text = match scalar { Value::String(s) => s.clone(), other => other.to_string() };
+			// a bare scalar document has no path to label it with
+			if path.is_empty() { out.push_str(&format!("{}\n", text)); } else { out.push_str(&format!("{}: {}\n", path, text)); }
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_kind_from_type_name_and_bytes() {
+		assert_eq!(DocKind::detect("text/html; charset=utf-8", "x", b"").unwrap(), DocKind::Html);
+		assert_eq!(DocKind::detect("application/ld+json", "x", b"").unwrap(), DocKind::Json);
+		assert_eq!(DocKind::detect("text/plain", "https://h/README.md?raw=1", b"").unwrap(), DocKind::Markdown);
+		assert_eq!(DocKind::detect("", "notes.txt", b"").unwrap(), DocKind::Text);
+		assert_eq!(DocKind::detect("application/octet-stream", "download", b"%PDF-1.7 ...").unwrap(), DocKind::Pdf);
+		assert_eq!(DocKind::detect("", "data", b" [1, 2]").unwrap(), DocKind::Json);
+		assert_eq!(DocKind::detect("", "page", b"<!DOCTYPE html><p>hi").unwrap(), DocKind::Html);
+		assert!(DocKind::detect("image/png", "a.png", b"").is_err());
+		assert!(DocKind::detect("", "blob", b"\x89PNG\0\0").is_err());
+	}
+
+	#[test]
+	fn extracts_each_kind() {
+		let html = extract("text/html", "p", b"<html><head><title>T</title></head><body><h1>Hi</h1><p> there </p></body></html>").unwrap();
+		assert_eq!(html, "Hi\nthere\n");
+		assert_eq!(extract("text/plain", "a.txt", b"plain words").unwrap(), "plain words");
+		assert_eq!(extract("text/markdown", "a", b"# Title\n\nSome *text*.").unwrap(), "Title\n\nSome text.\n");
+		assert_eq!(extract("application/json", "a", br#"{"a": {"b": [1, "two"]}}"#).unwrap(), "a.b[0]: 1\na.b[1]: two\n");
+		assert!(extract("application/json", "a", b"{not json").is_err());
+	}
+
+	#[test]
+	fn decodes_declared_and_bom_encodings() {
+		// "café" in Latin-1
+		assert_eq!(extract("text/plain; charset=iso-8859-1", "a", b"caf\xe9").unwrap(), "café");
+		assert_eq!(decode_text("text/plain", b"\xef\xbb\xbfhello"), "hello");
+	}
+
+	#[test]
+	fn renders_markdown_structure() {
+		let md = "# Heading\n\nIntro line\nsecond line\n\n- one\n- two\n  1. nested\n  2. again\n\n```\ncode block\n```\n\n---\n\nUse `cargo` and [links](http://x).";
+		assert_eq!(render_markdown(md), "Heading\n\nIntro line\nsecond line\n\n- one\n- two\n  1. nested\n  2. again\n\ncode block\n\nUse cargo and links.\n");
+		let ordered = render_markdown("3. c\n4. d");
+		assert_eq!(ordered, "3. c\n4. d\n");
+	}
+
+	#[test]
+	fn flattens_json_paths() {
+		let value = serde_json::json!({"name": "Mily", "tags": ["a", "b"], "nested": {"ok": true, "n": null, "deep": [{"x": 1.5}]}});
+		assert_eq!(flatten_json(&value), "name: Mily\nnested.deep[0].x: 1.5\nnested.n: null\nnested.ok: true\ntags[0]: a\ntags[1]: b\n");
+		assert_eq!(flatten_json(&serde_json::json!([1, [2]])), "[0]: 1\n[1][0]: 2\n");
+		assert_eq!(flatten_json(&serde_json::json!("bare")), "bare\n");
+		assert_eq!(flatten_json(&serde_json::json!(42)), "42\n");
+		assert_eq!(flatten_json(&serde_json::json!({})), "");
+	}
+}
\ No newline at end of file
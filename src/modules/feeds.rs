@@ -1,16 +1,246 @@
 #![cfg(feature = "feeds")]
-use anyhow::Result;
-use rss::Channel;
-
-pub async fn fetch_feed_text(url: &str) -> Result<Vec<String>> {
-	let body = reqwest::get(url).await?.bytes().await?;
-	let ch = Channel::read_from(&body[..])?;
-	let mut items = Vec::new();
-	for i in ch.items() {
-		let title = i.title().unwrap_or("");
-		let desc = i.description().unwrap_or("");
-		let content = format!("{}\n{}", title, desc).trim().to_string();
-		if !content.is_empty() { items.push(content); }
-	}
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::agent::Agent;
+use crate::memory;
+use crate::modules::{extract, web};
+use crate::settings::Settings;
+
+// per feed; old ids fall off once a feed has rotated them out anyway
+const MAX_SEEN_PER_FEED: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+	pub id: String,
+	pub title: String,
+	pub link: Option<String>,
+	pub published: Option<DateTime<Utc>>,
+	pub text: String,
+}
+
+/// Fetch and parse an RSS 0.9x/1.0/2.0, Atom or JSON Feed document, newest items first.
+pub async fn fetch_feed(settings: &Settings, url: &str) -> Result<Vec<FeedItem>> {
+	let resp = web::client(settings)?.get(url).send().await?;
+	if !resp.status().is_success() { return Err(anyhow!("Feed fetch failed: {}", resp.status())); }
+	parse_feed(&resp.bytes().await?)
+}
+
+pub fn parse_feed(body: &[u8]) -> Result<Vec<FeedItem>> {
+	let feed = feed_rs::parser::parse(body).map_err(|e| anyhow!("Feed parse failed: {}", e))?;
+	let mut items: Vec<FeedItem> = feed
+		.entries
+		.into_iter()
+		.map(|e| {
+			let title = e.title.map(|t| t.content).unwrap_or_default();
+			let body = e
+				.content
+				.and_then(|c| c.body)
+				.or_else(|| e.summary.map(|s| s.content))
+				.unwrap_or_default();
+			let body = if body.contains('<') { extract::extract_text_from_html(&body) } else { body };
+			FeedItem {
+				id: e.id,
+				title: title.clone(),
+				link: e.links.first().map(|l| l.href.clone()),
+				published: e.published.or(e.updated),
+				text: format!("{}\n{}", title, body).trim().to_string(),
+			}
+		})
+		.filter(|i| !i.text.is_empty() || i.link.is_some())
+		.collect();
+	items.sort_by_key(|i| std::cmp::Reverse(i.published));
 	Ok(items)
-} 
\ No newline at end of file
+}
+
+/// Item ids already learned, keyed by feed URL.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SeenStore {
+	#[serde(skip)]
+	path: PathBuf,
+	feeds: BTreeMap<String, VecDeque<String>>,
+}
+
+impl SeenStore {
+	pub fn open_default() -> Result<Self> {
+		Self::open(memory::data_dir()?.join("feeds_seen.json"))
+	}
+
+	pub fn open(path: PathBuf) -> Result<Self> {
+		let mut store = match fs::read_to_string(&path) {
+			Ok(s) => serde_json::from_str::<Self>(&s)?,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+			Err(e) => return Err(e.into()),
+		};
+		store.path = path;
+		Ok(store)
+	}
+
+	pub fn is_seen(&self, feed: &str, id: &str) -> bool {
+		self.feeds.get(feed).is_some_and(|ids| ids.iter().any(|s| s == id))
+	}
+
+	pub fn mark_seen(&mut self, feed: &str, id: &str) {
+		let ids = self.feeds.entry(feed.to_string()).or_default();
+		ids.push_back(id.to_string());
+		while ids.len() > MAX_SEEN_PER_FEED { ids.pop_front(); }
+	}
+
+	pub fn save(&self) -> Result<()> {
+		fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+}
+
+/// Learn up to `feed_max_items` unseen items from one feed. Returns how many were learned.
+pub async fn learn_from_feed(agent: &mut Agent, settings: &Settings, seen: &mut SeenStore, url: &str) -> Result<usize> {
+	let items = fetch_feed(settings, url).await?;
+	let max = settings.feed_max_items.unwrap_or(5);
+	let fetch_full = settings.feed_fetch_full.unwrap_or(false);
+	let mut learned = 0;
+	let fresh: Vec<FeedItem> = items.into_iter().filter(|i| !seen.is_seen(url, &i.id)).take(max).collect();
+	for item in fresh {
+		let mut text = item.text.clone();
+		if fetch_full {
+			if let Some(link) = &item.link {
+				match web::fetch_text(settings, link).await {
+					Ok(full) if !full.trim().is_empty() => text = format!("{}\n{}", item.title, full),
+					Ok(_) => {}
+					Err(e) => eprintln!("Article fetch failed {}: {}", link, e),
+				}
+			}
+		}
+		let source = item.link.as_deref().unwrap_or(url);
+		agent.summarize_and_learn_dated(source, item.published, &text).await?;
+		seen.mark_seen(url, &item.id);
+		seen.save()?;
+		learned += 1;
+	}
+	Ok(learned)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+
+	const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+	<title>Example</title><id>urn:feed</id><updated>2024-05-02T10:00:00Z</updated>
+	<entry>
+		<id>urn:a1</id><title>Older</title><link href="https://example.com/a1"/>
+		<updated>2024-05-01T10:00:00Z</updated><summary>Plain summary</summary>
+	</entry>
+	<entry>
+		<id>urn:a2</id><title>Newer</title><link href="https://example.com/a2"/>
+		<published>2024-05-02T09:00:00Z</published><updated>2024-05-02T09:30:00Z</updated>
+		<content type="html">&lt;p&gt;Hello &lt;b&gt;world&lt;/b&gt;&lt;/p&gt;</content>
+	</entry>
+</feed>"#;
+
+	const RSS1: &str = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+	<channel rdf:about="https://example.com/"><title>Ex</title><link>https://example.com/</link><description>d</description></channel>
+	<item rdf:about="https://example.com/r1"><title>First</title><link>https://example.com/r1</link><description>One</description><dc:date>2024-03-01T00:00:00Z</dc:date></item>
+	<item rdf:about="https://example.com/r2"><title>Second</title><link>https://example.com/r2</link><description>Two</description><dc:date>2024-03-02T00:00:00Z</dc:date></item>
+</rdf:RDF>"#;
+
+	const JSON_FEED: &str = r#"{"version": "https://jsonfeed.org/version/1.1", "title": "J", "items": [
+		{"id": "j1", "url": "https://example.com/j1", "title": "Jay", "content_text": "Text body", "date_published": "2024-01-01T00:00:00Z"},
+		{"id": "j2", "content_html": "<p>Only <i>html</i></p>"},
+		{"id": "j3"}
+	]}"#;
+
+	const RSS2: &str = r#"<?xml version="1.0"?><rss version="2.0"><channel><title>R</title><link>https://example.com/</link><description>d</description>
+		<item><guid>g1</guid><title>Item</title><link>https://example.com/g1</link><description>Body</description></item>
+	</channel></rss>"#;
+
+	fn date(s: &str) -> Option<DateTime<Utc>> {
+		Some(s.parse().unwrap())
+	}
+
+	#[test]
+	fn parses_atom_newest_first_with_html_stripped() {
+		let items = parse_feed(ATOM.as_bytes()).unwrap();
+		assert_eq!(items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), ["urn:a2", "urn:a1"]);
+		assert_eq!(items[0].published, date("2024-05-02T09:00:00Z"));
+		assert_eq!(items[0].link.as_deref(), Some("https://example.com/a2"));
+		// markup is dropped, one text node per line
+		assert_eq!(items[0].text, "Newer\nHello\nworld");
+		// updated stands in for a missing published date
+		assert_eq!(items[1].published, date("2024-05-01T10:00:00Z"));
+		assert_eq!(items[1].text, "Older\nPlain summary");
+	}
+
+	#[test]
+	fn parses_rss_1() {
+		let items = parse_feed(RSS1.as_bytes()).unwrap();
+		assert_eq!(items.iter().map(|i| i.title.as_str()).collect::<Vec<_>>(), ["Second", "First"]);
+		assert_eq!(items[0].link.as_deref(), Some("https://example.com/r2"));
+		assert_eq!(items[0].text, "Second\nTwo");
+		assert!(!items[0].id.is_empty() && items[0].id != items[1].id);
+	}
+
+	#[test]
+	fn parses_json_feed_and_drops_empty_items() {
+		let items = parse_feed(JSON_FEED.as_bytes()).unwrap();
+		assert_eq!(items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), ["j1", "j2"]);
+		assert_eq!(items[0].text, "Jay\nText body");
+		assert_eq!(items[0].published, date("2024-01-01T00:00:00Z"));
+		assert_eq!(items[1].text, "Only\nhtml");
+		assert!(parse_feed(b"not a feed").is_err());
+	}
+
+	#[test]
+	fn seen_store_keeps_the_newest_ids_per_feed() {
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join("seen.json");
+		let mut store = SeenStore::open(path.clone()).unwrap();
+		for i in 0..MAX_SEEN_PER_FEED + 5 { store.mark_seen("a", &format!("id{}", i)); }
+		store.mark_seen("b", "id0");
+		store.save().unwrap();
+
+		let store = SeenStore::open(path).unwrap();
+		assert_eq!(store.feeds["a"].len(), MAX_SEEN_PER_FEED);
+		assert!(!store.is_seen("a", "id4"));
+		assert!(store.is_seen("a", "id5"));
+		assert!(store.is_seen("a", &format!("id{}", MAX_SEEN_PER_FEED + 4)));
+		assert!(store.is_seen("b", "id0"));
+		assert!(!store.is_seen("c", "id0"));
+	}
+
+	/// Answer one request with `status` and `body`, handing back the request head.
+	fn stub_server(status: &'static str, body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = format!("http://{}", listener.local_addr().unwrap());
+		let handle = std::thread::spawn(move || {
+			let (mut conn, _) = listener.accept().unwrap();
+			let mut buf = [0u8; 4096];
+			let n = conn.read(&mut buf).unwrap();
+			write!(conn, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body).unwrap();
+			String::from_utf8_lossy(&buf[..n]).to_string()
+		});
+		(addr, handle)
+	}
+
+	#[tokio::test]
+	async fn fetch_feed_sends_the_user_agent() {
+		let settings = Settings { web_user_agent: Some("TestAgent/1".to_string()), ..Default::default() };
+		let (addr, server) = stub_server("200 OK", RSS2);
+		let items = fetch_feed(&settings, &format!("{}/feed.xml", addr)).await.unwrap();
+		let request = server.join().unwrap().to_lowercase();
+		assert!(request.contains("user-agent: testagent/1\r\n"), "{}", request);
+		assert_eq!(items.len(), 1);
+		assert_eq!(items[0].id, "g1");
+
+		let (addr, server) = stub_server("404 Not Found", "");
+		let err = fetch_feed(&settings, &addr).await.unwrap_err();
+		server.join().unwrap();
+		assert!(err.to_string().contains("404"), "{}", err);
+	}
+}
\ No newline at end of file
@@ -0,0 +1,248 @@
+#![cfg(feature = "ingest")]
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::agent::Agent;
+use crate::memory;
+use crate::modules::extract;
+use crate::modules::policy::{Access, PathPolicy};
+use crate::settings::Settings;
+
+/// Used when `ingest_max_file_bytes` is not set
+const DEFAULT_MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+// Source files are ingested verbatim as plain text.
+const SOURCE_EXTS: &[&str] = &[
+	"rs", "py", "js", "ts", "tsx", "jsx", "go", "java", "kt", "c", "h", "cpp", "hpp", "cs", "rb", "php",
+	"swift", "sh", "ps1", "sql", "toml", "yaml", "yml", "ini", "cfg", "xml",
+];
+const DOC_EXTS: &[&str] = &["txt", "text", "md", "markdown", "pdf", "html", "htm", "json", "csv", "rst", "log"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileState {
+	pub mtime: u64,
+	pub sha256: String,
+	pub ingested_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Per-file mtime and content hash from previous runs, so re-ingestion only touches changed files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IngestState {
+	pub files: BTreeMap<PathBuf, FileState>,
+}
+
+/// Result of comparing a file with the last run.
+pub enum Freshness {
+	Unchanged,
+	Changed { bytes: Vec<u8>, sha256: String },
+}
+
+impl IngestState {
+	pub fn load(path: &Path) -> Result<Self> {
+		match fs::read_to_string(path) {
+			Ok(s) => Ok(serde_json::from_str(&s)?),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+			Err(e) => Err(e.into()),
+		}
+	}
+
+	pub fn save(&self, path: &Path) -> Result<()> {
+		fs::write(path, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+
+	/// Compare `path` with the last run. An unchanged mtime means unchanged without reading the file;
+	/// otherwise the content hash decides, and a file that was only touched gets its new mtime recorded.
+	pub fn check(&mut self, path: &Path, mtime: u64, force: bool) -> Result<Freshness> {
+		if !force && self.files.get(path).is_some_and(|p| p.mtime == mtime) { return Ok(Freshness::Unchanged); }
+		let bytes = fs::read(path)?;
+		let sha256 = format!("{:x}", Sha256::digest(&bytes));
+		if let Some(prev) = self.files.get_mut(path).filter(|p| !force && p.sha256 == sha256) {
+			prev.mtime = mtime;
+			return Ok(Freshness::Unchanged);
+		}
+		Ok(Freshness::Changed { bytes, sha256 })
+	}
+}
+
+pub async fn run(settings: Settings, patterns: &[String], force: bool) -> Result<()> {
+	let mut policy = PathPolicy::from_settings(&settings)?;
+	policy.max_file_bytes = settings.ingest_max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES);
+	let files = collect_files(&policy, patterns)?;
+	if files.is_empty() {
+		println!("No ingestible files matched");
+		return Ok(());
+	}
+	let state_path = memory::data_dir()?.join("ingest_state.json");
+	let mut state = IngestState::load(&state_path)?;
+	let mut agent = Agent::new(settings)?;
+	let (mut learned, mut skipped) = (0usize, 0usize);
+	for path in files {
+		if let Err(e) = policy.check_size(&path) {
+			eprintln!("Skip {}", e);
+			continue;
+		}
+		let mtime = fs::metadata(&path)?.modified()?.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+		let (bytes, sha256) = match state.check(&path, mtime, force)? {
+			Freshness::Unchanged => { skipped += 1; continue; }
+			Freshness::Changed { bytes, sha256 } => (bytes, sha256),
+		};
+		let text = match extract_file(&path, &bytes) {
+			Ok(t) if !t.trim().is_empty() => t,
+			Ok(_) => { eprintln!("Skip {} (no text)", path.display()); continue; }
+			Err(e) => { eprintln!("Skip {}: {}", path.display(), e); continue; }
+		};
+		agent.summarize_and_learn(&path.display().to_string(), &text).await?;
+		state.files.insert(path.clone(), FileState { mtime, sha256, ingested_at: chrono::Utc::now() });
+		state.save(&state_path)?;
+		learned += 1;
+		println!("Learned from {}", path.display());
+	}
+	state.save(&state_path)?;
+	println!("Ingest done. {} learned, {} unchanged.", learned, skipped);
+	Ok(())
+}
+
+/// Expand paths, directories and glob patterns into supported files the path policy lets us read:
+/// under `allow_dirs` or `read_only_dirs` and not matching `deny_globs`.
+pub fn collect_files(policy: &PathPolicy, patterns: &[String]) -> Result<Vec<PathBuf>> {
+	let mut out = Vec::new();
+	for pattern in patterns {
+		let matches: Vec<PathBuf> = if Path::new(pattern).exists() {
+			vec![PathBuf::from(pattern)]
+		} else {
+			glob::glob(pattern)?.filter_map(|p| p.ok()).collect()
+		};
+		if matches.is_empty() { return Err(anyhow!("Nothing matches {}", pattern)); }
+		for m in matches {
+			let canon = policy.check(&m, Access::Read)?;
+			if canon.is_dir() {
+				for entry in walkdir::WalkDir::new(&canon).into_iter().filter_entry(|e| (e.depth() == 0 || !is_hidden(e.path())) && !policy.is_denied(e.path())) {
+					let entry = entry?;
+					if entry.file_type().is_file() && is_supported(entry.path()) { out.push(entry.into_path()); }
+				}
+			} else if is_supported(&canon) {
+				out.push(canon);
+			}
+		}
+	}
+	out.sort();
+	out.dedup();
+	Ok(out)
+}
+
+pub fn extract_file(path: &Path, bytes: &[u8]) -> Result<String> {
+	let name = path.to_string_lossy();
+	if ext_of(path).is_some_and(|e| SOURCE_EXTS.contains(&e.as_str())) {
+		return Ok(extract::decode_text("text/plain", bytes));
+	}
+	extract::extract("", &name, bytes)
+}
+
+fn is_supported(path: &Path) -> bool {
+	ext_of(path).is_some_and(|e| SOURCE_EXTS.contains(&e.as_str()) || DOC_EXTS.contains(&e.as_str()))
+}
+
+fn is_hidden(path: &Path) -> bool {
+	path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.') && n.len() > 1)
+}
+
+fn ext_of(path: &Path) -> Option<String> {
+	path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::TempDir;
+
+	/// root/ (read-write) and ro/ (read-only) with supported files, some of them denied, and a
+	/// directory outside both.
+	fn tree() -> (TempDir, PathBuf, PathPolicy) {
+		let tmp = tempfile::tempdir().unwrap();
+		let base = fs::canonicalize(tmp.path()).unwrap();
+		for (path, text) in [
+			("root/notes.md", "notes"),
+			("root/conf/app.toml", "name = 'x'"),
+			("root/conf/db.ini", "password=1"),
+			("root/secrets/creds.yaml", "token: 1"),
+			("ro/readme.txt", "read me"),
+			("outside/x.md", "x"),
+		] {
+			let path = base.join(path);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, text).unwrap();
+		}
+		let deny = ["**/secrets/**".to_string(), "**/*.ini".to_string()];
+		let policy = PathPolicy::new(&[base.join("root")], &[base.join("ro")], &deny, 1024).unwrap();
+		(tmp, base, policy)
+	}
+
+	fn collect(policy: &PathPolicy, patterns: &[PathBuf]) -> Result<Vec<PathBuf>> {
+		collect_files(policy, &patterns.iter().map(|p| p.display().to_string()).collect::<Vec<_>>())
+	}
+
+	#[test]
+	fn collect_files_follows_the_path_policy() {
+		let (_tmp, base, policy) = tree();
+		assert_eq!(collect(&policy, &[base.join("root")]).unwrap(), [base.join("root/conf/app.toml"), base.join("root/notes.md")]);
+		assert_eq!(collect(&policy, &[base.join("ro")]).unwrap(), [base.join("ro/readme.txt")]);
+		assert_eq!(collect(&policy, &[base.join("root/*/*.toml")]).unwrap(), [base.join("root/conf/app.toml")]);
+
+		let err = collect(&policy, &[base.join("root/secrets/creds.yaml")]).unwrap_err();
+		assert!(err.to_string().contains("deny_globs"), "{}", err);
+		let err = collect(&policy, &[base.join("root/conf/*.ini")]).unwrap_err();
+		assert!(err.to_string().contains("deny_globs"), "{}", err);
+		let err = collect(&policy, &[base.join("outside")]).unwrap_err();
+		assert!(err.to_string().contains("outside allow_dirs"), "{}", err);
+	}
+
+	#[test]
+	fn collect_files_skips_hidden_and_unsupported_entries_below_the_root() {
+		let (_tmp, base, _) = tree();
+		let root = base.join("root");
+		for path in [".cache/old.md", ".dot.md", "image.png", ".notes/a.md"] {
+			let path = root.join(path);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, "x").unwrap();
+		}
+		let policy = PathPolicy::new(std::slice::from_ref(&root), &[], &[], 1024).unwrap();
+		assert_eq!(collect(&policy, std::slice::from_ref(&root)).unwrap(), [root.join("conf/app.toml"), root.join("conf/db.ini"), root.join("notes.md"), root.join("secrets/creds.yaml")]);
+		// a hidden directory given directly is walked; so is a hidden file named directly
+		assert_eq!(collect(&policy, &[root.join(".notes")]).unwrap(), [root.join(".notes/a.md")]);
+		assert_eq!(collect(&policy, &[root.join(".dot.md"), root.join("notes.md"), root.join("notes.md")]).unwrap(), [root.join(".dot.md"), root.join("notes.md")]);
+		assert!(collect(&policy, &[root.join("image.png")]).unwrap().is_empty());
+		let err = collect(&policy, &[root.join("*.nope")]).unwrap_err();
+		assert!(err.to_string().starts_with("Nothing matches"), "{}", err);
+	}
+
+	#[test]
+	fn state_reads_files_only_when_their_mtime_changes() {
+		let tmp = tempfile::tempdir().unwrap();
+		let file = tmp.path().join("a.md");
+		fs::write(&file, "one").unwrap();
+		let mut state = IngestState::default();
+		let Freshness::Changed { sha256, .. } = state.check(&file, 10, false).unwrap() else { panic!("a new file is changed") };
+		state.files.insert(file.clone(), FileState { mtime: 10, sha256, ingested_at: chrono::Utc::now() });
+
+		// the same mtime is trusted without reading the content
+		fs::write(&file, "two").unwrap();
+		assert!(matches!(state.check(&file, 10, false).unwrap(), Freshness::Unchanged));
+		assert!(matches!(state.check(&file, 11, false).unwrap(), Freshness::Changed { .. }));
+		// touched with the content as before: unchanged, and the new mtime is kept
+		fs::write(&file, "one").unwrap();
+		assert!(matches!(state.check(&file, 12, false).unwrap(), Freshness::Unchanged));
+		assert_eq!(state.files[&file].mtime, 12);
+		assert!(matches!(state.check(&file, 12, true).unwrap(), Freshness::Changed { .. }));
+
+		let path = tmp.path().join("state.json");
+		state.save(&path).unwrap();
+		assert_eq!(IngestState::load(&path).unwrap().files[&file].mtime, 12);
+		assert!(IngestState::load(&tmp.path().join("missing.json")).unwrap().files.is_empty());
+	}
+}
\ No newline at end of file
@@ -3,6 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::settings::Settings;
 
+/// Default multimodal model when `vision_model` is unset
+#[cfg(feature = "vision")]
+const VISION_MODEL: &str = "llava";
+
 #[derive(Debug, Clone)]
 pub enum LlmProvider {
 	Endpoint,
@@ -53,11 +57,96 @@ impl LlmClient {
 			#[cfg(feature = "llm-ollama")]
 			LlmProvider::Ollama => self.generate_via_ollama(prompt).await,
 			#[cfg(feature = "llm-llama")]
-			LlmProvider::LlamaRs => self.generate_via_llama(prompt).await,
+			LlmProvider::LlamaRs => self.generate_via_llama(prompt, &mut |_| {}).await,
 			LlmProvider::Offline => Ok("[offline] Connect a local LLM or set llm_endpoint".to_string()),
 		}
 	}
 
+	/// Like `generate`, but hands text to `on_text` as the model produces it.
+	/// Providers without streaming deliver the whole reply in one piece.
+	pub async fn generate_stream(&self, prompt: &str, on_text: &mut dyn FnMut(&str)) -> Result<String> {
+		match self.provider {
+			#[cfg(feature = "llm-ollama")]
+			LlmProvider::Ollama => self.stream_via_ollama(prompt, on_text).await,
+			#[cfg(feature = "llm-llama")]
+			LlmProvider::LlamaRs => self.generate_via_llama(prompt, on_text).await,
+			_ => {
+				let text = self.generate(prompt).await?;
+				on_text(&text);
+				Ok(text)
+			}
+		}
+	}
+
+	/// Describe a JPEG image with a multimodal model: the OpenAI-compatible `vision_url` if set,
+	/// otherwise the configured Ollama or OpenAI provider.
+	#[cfg(feature = "vision")]
+	pub async fn describe_image(&self, prompt: &str, jpeg: &[u8]) -> Result<String> {
+		use base64::Engine;
+		let image = base64::engine::general_purpose::STANDARD.encode(jpeg);
+		if let Some(url) = &self.settings.vision_url {
+			let model = self.settings.vision_model.as_deref().unwrap_or(VISION_MODEL);
+			return self.vision_via_openai_compat(url, self.settings.vision_api_key.as_deref(), model, prompt, &image).await;
+		}
+		match self.provider {
+			#[cfg(feature = "llm-ollama")]
+			LlmProvider::Ollama => self.vision_via_ollama(prompt, &image).await,
+			#[cfg(feature = "llm-openai")]
+			LlmProvider::OpenAi => {
+				let model = self.settings.vision_model.clone().or_else(|| self.settings.openai_model.clone()).unwrap_or_else(|| "gpt-4o-mini".to_string());
+				self.vision_via_openai_compat("https://api.openai.com/v1", self.settings.openai_api_key.as_deref(), &model, prompt, &image).await
+			}
+			_ => Err(anyhow!("No vision backend: set vision_url, or ollama_url with a multimodal vision_model")),
+		}
+	}
+
+	/// Chat completion with the image as a data-URL content part.
+	#[cfg(feature = "vision")]
+	async fn vision_via_openai_compat(&self, base: &str, api_key: Option<&str>, model: &str, prompt: &str, image: &str) -> Result<String> {
+		#[derive(Deserialize)]
+		struct Message { content: Option<String> }
+		#[derive(Deserialize)]
+		struct Choice { message: Message }
+		#[derive(Deserialize)]
+		struct Resp { choices: Vec<Choice> }
+		let url = format!("{}/chat/completions", base.trim_end_matches('/'));
+		let body = serde_json::json!({
+			"model": model,
+			"temperature": self.settings.temperature.unwrap_or(0.6),
+			"messages": [{
+				"role": "user",
+				"content": [
+					{ "type": "text", "text": prompt },
+					{ "type": "image_url", "image_url": { "url": format!("data:image/jpeg;base64,{}", image) } },
+				],
+			}],
+		});
+		let mut req = reqwest::Client::new().post(&url).json(&body);
+		if let Some(key) = api_key { req = req.bearer_auth(key); }
+		let resp = req.send().await?;
+		if !resp.status().is_success() { return Err(anyhow!("Vision request failed: {}", resp.status())); }
+		let data: Resp = resp.json().await?;
+		data.choices.into_iter().next().and_then(|c| c.message.content).ok_or_else(|| anyhow!("no choices"))
+	}
+
+	/// llava-style models take base64 images next to the prompt.
+	#[cfg(all(feature = "vision", feature = "llm-ollama"))]
+	async fn vision_via_ollama(&self, prompt: &str, image: &str) -> Result<String> {
+		#[derive(Serialize)]
+		struct Req<'a> { model: &'a str, prompt: &'a str, images: [&'a str; 1], stream: bool, temperature: f32 }
+		#[derive(Deserialize)]
+		struct Resp { response: String }
+		let base = self.settings.ollama_url.clone().unwrap_or_else(|| "http://127.0.0.1:11434".to_string());
+		let model = self.settings.vision_model.as_deref().unwrap_or(VISION_MODEL);
+		let url = format!("{}/api/generate", base);
+		let temperature = self.settings.temperature.unwrap_or(0.6);
+		let client = reqwest::Client::new();
+		let resp = client.post(&url).json(&Req { model, prompt, images: [image], stream: false, temperature }).send().await?;
+		if !resp.status().is_success() { return Err(anyhow!("Ollama request failed: {}", resp.status())); }
+		let data: Resp = resp.json().await?;
+		Ok(data.response)
+	}
+
 	async fn generate_via_endpoint(&self, prompt: &str) -> Result<String> {
 		let url = self.settings.llm_endpoint.as_ref().ok_or_else(|| anyhow!("llm_endpoint not configured"))?;
 		#[derive(Serialize)]
@@ -110,8 +199,42 @@ impl LlmClient {
 		Ok(data.response)
 	}
 
+	/// Ollama streams one JSON object per line, each with the next piece of `response`, or an `error`
+	/// when generation fails part way (e.g. the model is not found).
+	#[cfg(feature = "llm-ollama")]
+	async fn stream_via_ollama(&self, prompt: &str, on_text: &mut dyn FnMut(&str)) -> Result<String> {
+		#[derive(Serialize)]
+		struct Req<'a> { model: &'a str, prompt: &'a str, stream: bool, temperature: f32 }
+		#[derive(Deserialize)]
+		struct Chunk { #[serde(default)] response: String, #[serde(default)] done: bool, error: Option<String> }
+		let base = self.settings.ollama_url.clone().unwrap_or_else(|| "http://127.0.0.1:11434".to_string());
+		let model = self.settings.ollama_model.clone().unwrap_or_else(|| "llama3.1:8b".to_string());
+		let url = format!("{}/api/generate", base);
+		let temperature = self.settings.temperature.unwrap_or(0.6);
+		let client = reqwest::Client::new();
+		let mut resp = client.post(&url).json(&Req { model: &model, prompt, stream: true, temperature }).send().await?;
+		if !resp.status().is_success() { return Err(anyhow!("Ollama request failed: {}", resp.status())); }
+		let mut buf: Vec<u8> = Vec::new();
+		let mut output = String::new();
+		while let Some(bytes) = resp.chunk().await? {
+			buf.extend_from_slice(&bytes);
+			while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+				let line: Vec<u8> = buf.drain(..=pos).collect();
+				if line.trim_ascii().is_empty() { continue; }
+				let chunk: Chunk = serde_json::from_slice(&line)?;
+				if let Some(error) = chunk.error { return Err(anyhow!("Ollama error: {}", error)); }
+				if !chunk.response.is_empty() {
+					on_text(&chunk.response);
+					output.push_str(&chunk.response);
+				}
+				if chunk.done { return Ok(output); }
+			}
+		}
+		Ok(output)
+	}
+
 	#[cfg(feature = "llm-llama")]
-	async fn generate_via_llama(&self, prompt: &str) -> Result<String> {
+	async fn generate_via_llama(&self, prompt: &str, on_text: &mut dyn FnMut(&str)) -> Result<String> {
 		use llama_rs::{InferenceParameters, InferenceSession, InferenceFeedback};
 		let llama = self.llama.as_ref().ok_or_else(|| anyhow!("LLaMA model not initialized"))?;
 		let mut session = InferenceSession::default();
@@ -125,8 +248,50 @@ impl LlmClient {
 			&params,
 			format!("{}", prompt),
 			None,
-			|t| { output.push_str(t); InferenceFeedback::Continue },
+			|t| { on_text(t); output.push_str(t); InferenceFeedback::Continue },
 		)?;
 		Ok(output)
 	}
-} 
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "llm-ollama"))]
+mod tests {
+	use super::*;
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+
+	/// Answer one request with a 200 streaming `body`.
+	fn stub_ollama(body: &'static str) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = format!("http://{}", listener.local_addr().unwrap());
+		std::thread::spawn(move || {
+			let (mut conn, _) = listener.accept().unwrap();
+			let mut buf = [0u8; 8192];
+			let _ = conn.read(&mut buf).unwrap();
+			write!(conn, "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+		});
+		addr
+	}
+
+	async fn stream(body: &'static str) -> (Result<String>, Vec<String>) {
+		let client = LlmClient::new(Settings { ollama_url: Some(stub_ollama(body)), ..Default::default() });
+		let mut pieces = Vec::new();
+		let result = client.generate_stream("hi", &mut |t| pieces.push(t.to_string())).await;
+		(result, pieces)
+	}
+
+	#[tokio::test]
+	async fn ollama_stream_collects_pieces() {
+		let (result, pieces) = stream("{\"response\":\"Hel\",\"done\":false}\n\n{\"response\":\"lo\",\"done\":false}\n{\"response\":\"\",\"done\":true}\n").await;
+		assert_eq!(result.unwrap(), "Hello");
+		assert_eq!(pieces, ["Hel", "lo"]);
+	}
+
+	#[tokio::test]
+	async fn ollama_stream_error_is_returned() {
+		let (result, pieces) = stream("{\"response\":\"Hi\",\"done\":false}\n{\"error\":\"model 'x' not found\"}\n").await;
+		let err = result.unwrap_err();
+		assert_eq!(err.to_string(), "Ollama error: model 'x' not found");
+		assert_eq!(pieces, ["Hi"]);
+	}
+}
\ No newline at end of file
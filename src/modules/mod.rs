@@ -1,17 +1,45 @@
-#[cfg(feature = "tts")]
+#[cfg(feature = "speech")]
 pub mod tts;
+#[cfg(feature = "tts-process")]
+pub mod tts_process;
+#[cfg(feature = "stt-vosk")]
+pub mod audio;
 #[cfg(feature = "stt-vosk")]
 pub mod stt;
+#[cfg(feature = "stt-vosk")]
+pub mod vad;
 #[cfg(feature = "camera")]
 pub mod camera;
-#[cfg(all(feature = "stt-vosk", feature = "tts"))]
+#[cfg(feature = "camera")]
+pub mod watch;
+#[cfg(feature = "vision")]
+pub mod vision;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+#[cfg(all(feature = "stt-vosk", feature = "speech"))]
 pub mod voice;
 #[cfg(feature = "web")]
 pub mod web;
+#[cfg(feature = "web")]
+pub mod extract;
+#[cfg(feature = "web")]
+pub mod sources;
+#[cfg(feature = "web")]
+pub mod search;
 pub mod llm;
-#[cfg(all(feature = "stt-vosk", feature = "tts"))]
+#[cfg(all(feature = "stt-vosk", feature = "speech"))]
 pub mod voicechat;
+#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+pub mod voicecmd;
 #[cfg(feature = "actions")]
 pub mod actions;
+#[cfg(feature = "actions")]
+pub mod undo;
+#[cfg(any(feature = "actions", feature = "ingest"))]
+pub mod policy;
+#[cfg(feature = "actions")]
+pub mod command;
 #[cfg(feature = "feeds")]
-pub mod feeds; 
\ No newline at end of file
+pub mod feeds;
+#[cfg(feature = "ingest")]
+pub mod ingest;
\ No newline at end of file
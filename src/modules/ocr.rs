@@ -0,0 +1,122 @@
+#![cfg(feature = "ocr")]
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::agent::Agent;
+use crate::settings::Settings;
+
+const DEFAULT_LANGUAGES: &str = "ind+eng";
+
+/// Text recognition for an image file.
+pub trait OcrBackend {
+	fn recognize(&self, image: &Path) -> Result<String>;
+}
+
+/// The backend chosen by `ocr_backend`; currently only "tesseract".
+pub fn backend(settings: &Settings) -> Result<Box<dyn OcrBackend>> {
+	match settings.ocr_backend.as_deref().unwrap_or("tesseract") {
+		"tesseract" => Ok(Box::new(Tesseract::new(settings))),
+		other => Err(anyhow!("Unknown ocr_backend: {}", other)),
+	}
+}
+
+/// A local `tesseract` binary, run once per image.
+pub struct Tesseract {
+	command: PathBuf,
+	languages: String,
+}
+
+impl Tesseract {
+	pub fn new(settings: &Settings) -> Self {
+		Self {
+			command: settings.ocr_command.clone().unwrap_or_else(|| "tesseract".into()),
+			languages: settings.ocr_languages.clone().unwrap_or_else(|| DEFAULT_LANGUAGES.to_string()),
+		}
+	}
+}
+
+impl OcrBackend for Tesseract {
+	fn recognize(&self, image: &Path) -> Result<String> {
+		let out = Command::new(&self.command)
+			.arg(image)
+			.arg("stdout")
+			.arg("-l")
+			.arg(&self.languages)
+			.output()
+			.map_err(|e| anyhow!("Failed to start {}: {}", self.command.display(), e))?;
+		if !out.status.success() {
+			return Err(anyhow!("{} failed on {}: {}", self.command.display(), image.display(), String::from_utf8_lossy(&out.stderr).trim()));
+		}
+		Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+	}
+}
+
+/// Save a camera frame losslessly for OCR and return its path.
+#[cfg(feature = "camera")]
+fn capture_frame(settings: &Settings) -> Result<PathBuf> {
+	let dir = crate::memory::data_dir()?.join("captures");
+	std::fs::create_dir_all(&dir)?;
+	let path = dir.join(format!("{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+	crate::modules::camera::snapshot(settings, &path.to_string_lossy())?;
+	Ok(path)
+}
+
+#[cfg(not(feature = "camera"))]
+fn capture_frame(_settings: &Settings) -> Result<PathBuf> {
+	Err(anyhow!("No image given and camera support is not built in (--features camera)"))
+}
+
+/// Recognize text in each image (or a fresh camera frame when none are given) and learn it,
+/// with the image path as the source.
+pub async fn run(settings: Settings, paths: &[String]) -> Result<()> {
+	let images: Vec<PathBuf> = match paths {
+		[] => vec![capture_frame(&settings)?],
+		_ => paths.iter().map(PathBuf::from).collect(),
+	};
+	let ocr = backend(&settings)?;
+	let mut agent = Agent::new(settings)?;
+	for image in images {
+		let text = match ocr.recognize(&image) {
+			Ok(t) if !t.is_empty() => t,
+			Ok(_) => { eprintln!("No text found in {}", image.display()); continue; }
+			Err(e) => { eprintln!("Skip {}: {}", image.display(), e); continue; }
+		};
+		println!("Text in {}:\n{}\n", image.display(), text);
+		let summary = agent.summarize_and_learn(&image.display().to_string(), &text).await?;
+		println!("Learned:\n{}", summary);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn backend_rejects_unknown_names() {
+		let settings = Settings { ocr_backend: Some("easyocr".to_string()), ..Default::default() };
+		match backend(&settings) {
+			Ok(_) => panic!("expected an error"),
+			Err(e) => assert_eq!(e.to_string(), "Unknown ocr_backend: easyocr"),
+		}
+	}
+
+	/// A fake tesseract that echoes its arguments, or fails for images named "bad*".
+	#[cfg(unix)]
+	#[test]
+	fn tesseract_runs_the_configured_command() {
+		use std::os::unix::fs::PermissionsExt;
+		let dir = tempfile::tempdir().unwrap();
+		let script = dir.path().join("tesseract");
+		std::fs::write(&script, "#!/bin/sh\ncase \"$(basename \"$1\")\" in bad*) echo \"  cannot read $1  \" >&2; exit 1;; esac\necho \"  $*  \"\n").unwrap();
+		std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+		let settings = Settings { ocr_command: Some(script.clone()), ocr_languages: Some("eng".to_string()), ..Default::default() };
+		let ocr = backend(&settings).unwrap();
+		assert_eq!(ocr.recognize(Path::new("/img/page.png")).unwrap(), "/img/page.png stdout -l eng");
+		let err = ocr.recognize(Path::new("/img/bad.png")).unwrap_err().to_string();
+		assert_eq!(err, format!("{} failed on /img/bad.png: cannot read /img/bad.png", script.display()));
+		let missing = Tesseract::new(&Settings { ocr_command: Some(dir.path().join("missing")), ..Default::default() });
+		assert!(missing.recognize(Path::new("a.png")).unwrap_err().to_string().starts_with("Failed to start"));
+	}
+}
\ No newline at end of file
@@ -0,0 +1,266 @@
+#![cfg(any(feature = "actions", feature = "ingest"))]
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::settings::Settings;
+
+/// Used when `deny_globs` is not set
+pub const DEFAULT_DENY_GLOBS: &[&str] = &["**/.ssh/**", "**/.gnupg/**", "**/*.key", "**/*.pem", "**/.env"];
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+	Read,
+	#[cfg_attr(not(feature = "actions"), allow(dead_code))]
+	Write,
+}
+
+/// Which files actions may touch: read-write under `allow_dirs`, read-only under `read_only_dirs`
+/// (which wins where both apply), never anything matching `deny_globs`.
+pub struct PathPolicy {
+	roots: Vec<PathBuf>,
+	read_only: Vec<PathBuf>,
+	deny: Vec<glob::Pattern>,
+	pub max_file_bytes: u64,
+}
+
+impl PathPolicy {
+	pub fn new(allow_dirs: &[PathBuf], read_only_dirs: &[PathBuf], deny_globs: &[String], max_file_bytes: u64) -> Result<Self> {
+		// roots that do not exist cannot contain anything; canonical forms make prefix checks sound
+		let canon = |dirs: &[PathBuf]| dirs.iter().filter_map(|d| fs::canonicalize(d).ok()).collect::<Vec<_>>();
+		let deny = deny_globs
+			.iter()
+			.map(|g| glob::Pattern::new(g).map_err(|e| anyhow!("Bad deny_globs pattern '{}': {}", g, e)))
+			.collect::<Result<_>>()?;
+		Ok(Self { roots: canon(allow_dirs), read_only: canon(read_only_dirs), deny, max_file_bytes })
+	}
+
+	pub fn from_settings(settings: &Settings) -> Result<Self> {
+		if settings.allow_dirs.is_none() && settings.read_only_dirs.is_none() { return Err(anyhow!("No allow_dirs configured")); }
+		let deny: Vec<String> = match &settings.deny_globs {
+			Some(globs) => globs.clone(),
+			None => DEFAULT_DENY_GLOBS.iter().map(|g| g.to_string()).collect(),
+		};
+		Self::new(
+			settings.allow_dirs.as_deref().unwrap_or_default(),
+			settings.read_only_dirs.as_deref().unwrap_or_default(),
+			&deny,
+			settings.actions_max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES),
+		)
+	}
+
+	/// Resolve `path`, following symlinks, and check it; returns the resolved path to operate on.
+	pub fn check(&self, path: &Path, access: Access) -> Result<PathBuf> {
+		let resolved = resolve(path)?;
+		self.check_resolved(path, &resolved, access)?;
+		Ok(resolved)
+	}
+
+	/// Like `check`, but for the directory entry itself: a symlink is not followed, so trashing one
+	/// moves the link rather than its target.
+	#[cfg_attr(not(feature = "actions"), allow(dead_code))]
+	pub fn check_entry(&self, path: &Path, access: Access) -> Result<PathBuf> {
+		let abs = absolute(path)?;
+		let (Some(parent), Some(name)) = (abs.parent(), abs.file_name()) else {
+			return Err(anyhow!("{} does not name a file or directory", path.display()));
+		};
+		let resolved = resolve(parent)?.join(name);
+		self.check_resolved(path, &resolved, access)?;
+		Ok(resolved)
+	}
+
+	/// Whether `resolved` is one of the configured directories itself rather than something in it.
+	#[cfg_attr(not(feature = "actions"), allow(dead_code))]
+	pub fn is_root(&self, resolved: &Path) -> bool {
+		self.roots.iter().chain(&self.read_only).any(|r| r == resolved)
+	}
+
+	pub fn is_denied(&self, path: &Path) -> bool {
+		// with a trailing separator "**/.ssh/**" also covers the .ssh directory itself
+		let as_dir = format!("{}{}", path.display(), std::path::MAIN_SEPARATOR);
+		self.deny.iter().any(|p| p.matches_path(path) || p.matches(&as_dir))
+	}
+
+	fn check_resolved(&self, requested: &Path, resolved: &Path, access: Access) -> Result<()> {
+		// the requested spelling too, so a symlink cannot give a denied name a harmless target
+		if self.is_denied(resolved) || self.is_denied(&absolute(requested)?) {
+			return Err(anyhow!("{} is blocked by deny_globs", requested.display()));
+		}
+		let read_only = self.read_only.iter().any(|r| resolved.starts_with(r));
+		let read_write = self.roots.iter().any(|r| resolved.starts_with(r));
+		match access {
+			_ if !read_only && !read_write => Err(anyhow!("{} is outside allow_dirs", requested.display())),
+			Access::Write if read_only => Err(anyhow!("{} is in a read-only directory", requested.display())),
+			_ => Ok(()),
+		}
+	}
+
+	pub fn check_size(&self, path: &Path) -> Result<()> {
+		let len = fs::metadata(path)?.len();
+		self.check_len(len).map_err(|_| anyhow!("{} is {} bytes; the limit is {}", path.display(), len, self.max_file_bytes))
+	}
+
+	pub fn check_len(&self, len: u64) -> Result<()> {
+		if len > self.max_file_bytes { return Err(anyhow!("{} bytes is over the {} byte limit", len, self.max_file_bytes)); }
+		Ok(())
+	}
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+	Ok(if path.is_absolute() { path.to_path_buf() } else { std::env::current_dir()?.join(path) })
+}
+
+/// Absolute, symlink-free form of `path`. An existing path is canonicalized (a dangling symlink is
+/// an error, since writing through it would create its target). For a new path the deepest existing
+/// ancestor is canonicalized and the rest must be plain names, so `..` cannot climb out unseen.
+pub fn resolve(path: &Path) -> Result<PathBuf> {
+	let abs = absolute(path)?;
+	if fs::symlink_metadata(&abs).is_ok() {
+		return fs::canonicalize(&abs).map_err(|e| anyhow!("Cannot resolve {}: {}", path.display(), e));
+	}
+	let mut existing = abs.clone();
+	let mut tail = Vec::new();
+	while fs::symlink_metadata(&existing).is_err() {
+		match existing.components().next_back() {
+			Some(Component::Normal(name)) => tail.push(name.to_os_string()),
+			Some(Component::CurDir) => {}
+			_ => return Err(anyhow!("Cannot resolve {}: '..' in a part that does not exist", path.display())),
+		}
+		if !existing.pop() { return Err(anyhow!("Cannot resolve {}", path.display())); }
+	}
+	let mut resolved = fs::canonicalize(&existing).map_err(|e| anyhow!("Cannot resolve {}: {}", path.display(), e))?;
+	resolved.extend(tail.iter().rev());
+	Ok(resolved)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use std::os::unix::fs::symlink;
+	use tempfile::TempDir;
+
+	/// A tree with a read-write root, a read-only dir inside it, and a directory outside both.
+	struct Fixture {
+		_tmp: TempDir,
+		root: PathBuf,
+		outside: PathBuf,
+		policy: PathPolicy,
+	}
+
+	fn fixture() -> Fixture {
+		let tmp = tempfile::tempdir().unwrap();
+		let base = fs::canonicalize(tmp.path()).unwrap();
+		let root = base.join("root");
+		let outside = base.join("outside");
+		fs::create_dir_all(root.join("docs/ro")).unwrap();
+		fs::create_dir_all(&outside).unwrap();
+		fs::write(root.join("docs/note.txt"), "hi").unwrap();
+		fs::write(root.join("docs/ro/kept.txt"), "keep").unwrap();
+		fs::write(outside.join("secret.txt"), "s").unwrap();
+		let deny: Vec<String> = DEFAULT_DENY_GLOBS.iter().map(|g| g.to_string()).collect();
+		let policy = PathPolicy::new(std::slice::from_ref(&root), &[root.join("docs/ro")], &deny, 10).unwrap();
+		Fixture { _tmp: tmp, root, outside, policy }
+	}
+
+	#[test]
+	fn allows_inside_root() {
+		let f = fixture();
+		assert_eq!(f.policy.check(&f.root.join("docs/note.txt"), Access::Write).unwrap(), f.root.join("docs/note.txt"));
+		// a new file in a new directory resolves through the existing ancestor
+		assert_eq!(f.policy.check(&f.root.join("new/dir/file.txt"), Access::Write).unwrap(), f.root.join("new/dir/file.txt"));
+		assert!(f.policy.check(&f.outside.join("secret.txt"), Access::Read).is_err());
+	}
+
+	#[test]
+	fn dotdot_through_missing_component_is_rejected() {
+		let f = fixture();
+		// "missing/.." would lexically stay inside, but cannot be resolved honestly
+		assert!(f.policy.check(&f.root.join("missing/../../outside/secret.txt"), Access::Read).is_err());
+		assert!(resolve(&f.root.join("missing/../x")).is_err());
+		// through existing directories it resolves, and then lands outside
+		assert!(f.policy.check(&f.root.join("docs/../../outside/secret.txt"), Access::Read).is_err());
+	}
+
+	#[test]
+	fn symlink_leaving_root_is_rejected() {
+		let f = fixture();
+		symlink(&f.outside, f.root.join("escape")).unwrap();
+		assert!(f.policy.check(&f.root.join("escape/secret.txt"), Access::Read).is_err());
+		assert!(f.policy.check(&f.root.join("escape/new.txt"), Access::Write).is_err());
+		// the link itself is inside, so it may be trashed without touching its target
+		assert_eq!(f.policy.check_entry(&f.root.join("escape"), Access::Write).unwrap(), f.root.join("escape"));
+	}
+
+	#[test]
+	fn dangling_symlink_is_rejected() {
+		let f = fixture();
+		symlink(f.outside.join("not-yet"), f.root.join("dangling")).unwrap();
+		assert!(f.policy.check(&f.root.join("dangling"), Access::Write).is_err());
+		assert!(f.policy.check_entry(&f.root.join("dangling"), Access::Write).is_ok());
+	}
+
+	#[test]
+	fn deny_globs_apply_to_name_and_target() {
+		let f = fixture();
+		fs::create_dir_all(f.root.join(".ssh")).unwrap();
+		fs::write(f.root.join(".ssh/id_ed25519"), "k").unwrap();
+		fs::write(f.root.join("server.pem"), "k").unwrap();
+		assert!(f.policy.check(&f.root.join(".ssh/id_ed25519"), Access::Read).is_err());
+		assert!(f.policy.check(&f.root.join(".ssh"), Access::Read).is_err());
+		// a harmless name pointing at a denied file
+		symlink(f.root.join("server.pem"), f.root.join("cert.txt")).unwrap();
+		assert!(f.policy.check(&f.root.join("cert.txt"), Access::Read).is_err());
+		// a denied name pointing at a harmless file
+		symlink(f.root.join("docs/note.txt"), f.root.join("note.key")).unwrap();
+		assert!(f.policy.check(&f.root.join("note.key"), Access::Read).is_err());
+	}
+
+	#[test]
+	fn read_only_dirs_take_precedence() {
+		let f = fixture();
+		let kept = f.root.join("docs/ro/kept.txt");
+		assert!(f.policy.check(&kept, Access::Read).is_ok());
+		assert!(f.policy.check(&kept, Access::Write).is_err());
+		assert!(f.policy.check(&f.root.join("docs/ro/new.txt"), Access::Write).is_err());
+		assert!(f.policy.check_entry(&kept, Access::Write).is_err());
+		assert!(f.policy.check(&f.root.join("docs/note.txt"), Access::Write).is_ok());
+	}
+
+	#[test]
+	fn roots_are_recognized() {
+		let f = fixture();
+		let root = f.policy.check_entry(&f.root, Access::Write).unwrap();
+		assert!(f.policy.is_root(&root));
+		assert!(f.policy.is_root(&f.root.join("docs/ro")));
+		assert!(!f.policy.is_root(&f.root.join("docs")));
+	}
+
+	#[test]
+	fn check_entry_needs_a_name() {
+		let f = fixture();
+		assert!(f.policy.check_entry(Path::new("/"), Access::Write).is_err());
+		assert!(f.policy.check_entry(&f.outside.join("secret.txt"), Access::Write).is_err());
+	}
+
+	#[test]
+	fn is_denied_matches_paths_and_directories() {
+		let f = fixture();
+		assert!(f.policy.is_denied(Path::new("/home/u/.ssh")));
+		assert!(f.policy.is_denied(Path::new("/home/u/.ssh/config")));
+		assert!(f.policy.is_denied(Path::new("/srv/app/.env")));
+		assert!(f.policy.is_denied(Path::new("/srv/tls/site.key")));
+		assert!(!f.policy.is_denied(Path::new("/srv/app/.envrc")));
+		assert!(!f.policy.is_denied(Path::new("/home/u/notes.txt")));
+	}
+
+	#[test]
+	fn size_limit() {
+		let f = fixture();
+		assert!(f.policy.check_len(10).is_ok());
+		assert!(f.policy.check_len(11).is_err());
+		fs::write(f.root.join("big.txt"), "0123456789ab").unwrap();
+		assert!(f.policy.check_size(&f.root.join("big.txt")).is_err());
+		assert!(f.policy.check_size(&f.root.join("docs/note.txt")).is_ok());
+	}
+}
\ No newline at end of file
@@ -0,0 +1,113 @@
+#![cfg(feature = "web")]
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use url::Url;
+
+use crate::modules::web;
+use crate::settings::Settings;
+
+// Phrases that suggest the answer depends on current information.
+const FRESHNESS_HINTS: &[&str] = &[
+	"terbaru", "terkini", "hari ini", "sekarang", "berita", "cuaca", "harga", "kurs", "jadwal", "skor",
+	"latest", "today", "current", "news", "weather", "price", "score", "this week", "minggu ini",
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchResult {
+	pub url: String,
+	#[serde(default)]
+	pub title: String,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+	#[serde(default)]
+	results: Vec<SearchResult>,
+}
+
+/// Decide whether a message should be answered from a web search, returning the query to run.
+/// `/search <q>` and `cari: <q>` always search; otherwise freshness phrases in a question trigger it.
+pub fn search_query(input: &str) -> Option<&str> {
+	let trimmed = input.trim();
+	for prefix in ["/search ", "cari:", "search:"] {
+		if trimmed.get(..prefix.len()).is_some_and(|p| p.eq_ignore_ascii_case(prefix)) {
+			let q = trimmed[prefix.len()..].trim();
+			return (!q.is_empty()).then_some(q);
+		}
+	}
+	// whole words only, so "current" does not fire on "concurrently"
+	let lower = trimmed.to_lowercase();
+	let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+	let has_hint = FRESHNESS_HINTS.iter().any(|h| {
+		let hint: Vec<&str> = h.split(' ').collect();
+		words.windows(hint.len()).any(|w| w == hint.as_slice())
+	});
+	has_hint.then_some(trimmed)
+}
+
+/// Query a SearXNG-compatible `/search?format=json` endpoint at `search_url`.
+pub async fn search(settings: &Settings, query: &str) -> Result<Vec<SearchResult>> {
+	let base = settings.search_url.as_ref().ok_or_else(|| anyhow!("search_url not configured"))?;
+	let mut url = Url::parse(base)?;
+	if !url.path().ends_with("/search") {
+		let path = format!("{}/search", url.path().trim_end_matches('/'));
+		url.set_path(&path);
+	}
+	url.query_pairs_mut().append_pair("q", query).append_pair("format", "json");
+	let resp = web::client(settings)?.get(url).send().await?;
+	if !resp.status().is_success() { return Err(anyhow!("Search request failed: {}", resp.status())); }
+	let data: SearchResponse = resp.json().await?;
+	Ok(data.results.into_iter().filter(|r| r.url.starts_with("http")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+
+	#[test]
+	fn prefixes_and_hints() {
+		assert_eq!(search_query("/search rust async"), Some("rust async"));
+		assert_eq!(search_query("CARI: harga emas"), Some("harga emas"));
+		assert_eq!(search_query("cari:"), None);
+		assert_eq!(search_query("berita hari ini?"), Some("berita hari ini?"));
+		assert_eq!(search_query("what is the weather in Bandung"), Some("what is the weather in Bandung"));
+		assert_eq!(search_query("how does concurrent code work"), None);
+		assert_eq!(search_query("I am currently learning Rust"), None);
+	}
+
+	#[test]
+	fn multibyte_input_does_not_panic() {
+		assert_eq!(search_query("日本語のテキスト"), None);
+		assert_eq!(search_query("🦀🦀 crabs"), None);
+		assert_eq!(search_query("é"), None);
+	}
+
+	/// Serve one canned JSON response and hand back the request line it received.
+	fn stub_server(body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = format!("http://{}", listener.local_addr().unwrap());
+		let handle = std::thread::spawn(move || {
+			let (mut conn, _) = listener.accept().unwrap();
+			let mut buf = [0u8; 4096];
+			let n = conn.read(&mut buf).unwrap();
+			let request = String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or_default().to_string();
+			write!(conn, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+			request
+		});
+		(addr, handle)
+	}
+
+	#[tokio::test]
+	async fn queries_searxng_endpoint() {
+		let (addr, server) = stub_server(r#"{"results":[{"url":"https://a.example/x","title":"A"},{"url":"ftp://b.example/","title":"B"},{"url":"http://c.example/"}]}"#);
+		let settings = Settings { search_url: Some(addr), ..Default::default() };
+		let results = search(&settings, "harga emas").await.unwrap();
+		let request = server.join().unwrap();
+		assert!(request.starts_with("GET /search?q=harga+emas&format=json "), "{}", request);
+		let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+		assert_eq!(urls, ["https://a.example/x", "http://c.example/"]);
+		assert_eq!(results[0].title, "A");
+	}
+}
\ No newline at end of file
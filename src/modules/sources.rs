@@ -0,0 +1,281 @@
+#![cfg(feature = "web")]
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::agent::Agent;
+use crate::modules::web;
+use crate::settings::{self, Settings};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+	#[default]
+	Page,
+	Feed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Source {
+	pub url: String,
+	#[serde(default)]
+	pub kind: SourceKind,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub title: Option<String>,
+	/// Overrides `learn_interval_secs` for this source
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub interval_secs: Option<u64>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tags: Vec<String>,
+	#[serde(default = "default_enabled")]
+	pub enabled: bool,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub last_fetch: Option<DateTime<Utc>>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub last_status: Option<String>,
+}
+
+fn default_enabled() -> bool { true }
+
+impl Source {
+	pub fn new(url: &str, kind: SourceKind) -> Self {
+		Self { url: url.to_string(), kind, title: None, interval_secs: None, tags: Vec::new(), enabled: true, last_fetch: None, last_status: None }
+	}
+
+	pub fn next_due(&self, default_interval: u64) -> DateTime<Utc> {
+		let interval = self.interval_secs.unwrap_or(default_interval);
+		match self.last_fetch {
+			Some(t) => t + Duration::seconds(interval as i64),
+			None => DateTime::<Utc>::MIN_UTC,
+		}
+	}
+}
+
+/// Learn sources kept in their own YAML file (`sources_path`, default `sources.yaml` next to config.yaml).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SourceList {
+	#[serde(skip)]
+	path: PathBuf,
+	#[serde(default)]
+	pub sources: Vec<Source>,
+}
+
+impl SourceList {
+	pub fn load(settings: &Settings) -> Result<Self> {
+		let path = match &settings.sources_path {
+			Some(p) => p.clone(),
+			None => settings::config_dir()?.join("sources.yaml"),
+		};
+		Self::read(path)
+	}
+
+	fn read(path: PathBuf) -> Result<Self> {
+		let mut list = match fs::read_to_string(&path) {
+			Ok(s) if !s.trim().is_empty() => serde_yaml::from_str::<Self>(&s)?,
+			Ok(_) => Self::default(),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+			Err(e) => return Err(e.into()),
+		};
+		list.path = path;
+		Ok(list)
+	}
+
+	pub fn save(&self) -> Result<()> {
+		fs::write(&self.path, serde_yaml::to_string(self)?)?;
+		Ok(())
+	}
+
+	/// Write only the fetch status of our sources into the file as it is now, so changes made by
+	/// `milyai sources` in the meantime survive; sources removed since are left out.
+	pub fn save_status(&self) -> Result<()> {
+		let mut current = Self::read(self.path.clone())?;
+		for source in &self.sources {
+			if let Some(c) = current.find_mut(&source.url) {
+				c.last_fetch = source.last_fetch;
+				c.last_status = source.last_status.clone();
+			}
+		}
+		current.save()
+	}
+
+	pub fn find_mut(&mut self, url: &str) -> Option<&mut Source> {
+		self.sources.iter_mut().find(|s| s.url == url)
+	}
+
+	pub fn add(&mut self, source: Source) -> Result<()> {
+		if self.sources.iter().any(|s| s.url == source.url) { return Err(anyhow!("Source already exists: {}", source.url)); }
+		self.sources.push(source);
+		Ok(())
+	}
+
+	pub fn remove(&mut self, url: &str) -> bool {
+		let before = self.sources.len();
+		self.sources.retain(|s| s.url != url);
+		self.sources.len() != before
+	}
+
+	/// Add feeds from an OPML document; folder names become tags. Returns the number of new sources.
+	pub fn import_opml(&mut self, xml: &str) -> Result<usize> {
+		let doc = opml::OPML::from_str(xml).map_err(|e| anyhow!("Invalid OPML: {}", e))?;
+		let mut found = Vec::new();
+		collect_outlines(&doc.body.outlines, &[], &mut found);
+		let mut added = 0;
+		for source in found {
+			if self.add(source).is_ok() { added += 1; }
+		}
+		Ok(added)
+	}
+
+	pub fn export_opml(&self) -> Result<String> {
+		let mut doc = opml::OPML {
+			head: Some(opml::Head { title: Some("MilyAI learn sources".to_string()), ..Default::default() }),
+			..Default::default()
+		};
+		for s in &self.sources {
+			let text = s.title.clone().unwrap_or_else(|| s.url.clone());
+			let mut outline = opml::Outline { text: text.clone(), title: Some(text), ..Default::default() };
+			match s.kind {
+				SourceKind::Feed => { outline.r#type = Some("rss".to_string()); outline.xml_url = Some(s.url.clone()); }
+				SourceKind::Page => { outline.r#type = Some("link".to_string()); outline.url = Some(s.url.clone()); }
+			}
+			if !s.tags.is_empty() { outline.category = Some(s.tags.join(",")); }
+			doc.body.outlines.push(outline);
+		}
+		doc.to_string().map_err(|e| anyhow!("OPML export failed: {}", e))
+	}
+}
+
+fn collect_outlines(outlines: &[opml::Outline], folders: &[String], out: &mut Vec<Source>) {
+	for o in outlines {
+		let (url, kind) = match (&o.xml_url, &o.url, &o.html_url) {
+			(Some(x), _, _) => (Some(x), SourceKind::Feed),
+			(None, Some(u), _) => (Some(u), SourceKind::Page),
+			_ => (None, SourceKind::Page),
+		};
+		if let Some(url) = url {
+			let mut source = Source::new(url, kind);
+			let title = o.title.clone().unwrap_or_else(|| o.text.clone());
+			if !title.is_empty() { source.title = Some(title); }
+			source.tags = folders.to_vec();
+			if let Some(cat) = &o.category {
+				for t in cat.split(',').map(|t| t.trim().trim_start_matches('/')).filter(|t| !t.is_empty()) {
+					if !source.tags.iter().any(|x| x == t) { source.tags.push(t.to_string()); }
+				}
+			}
+			out.push(source);
+		}
+		if !o.outlines.is_empty() {
+			let mut nested = folders.to_vec();
+			if url.is_none() && !o.text.is_empty() { nested.push(o.text.clone()); }
+			collect_outlines(&o.outlines, &nested, out);
+		}
+	}
+}
+
+/// `learn_urls` / `learn_feeds` from config.yaml as in-memory sources.
+pub fn from_settings(settings: &Settings) -> Vec<Source> {
+	let pages = settings.learn_urls.iter().flatten().map(|u| Source::new(u, SourceKind::Page));
+	#[cfg(feature = "feeds")]
+	let pages = pages.chain(settings.learn_feeds.iter().flatten().map(|u| Source::new(u, SourceKind::Feed)));
+	pages.collect()
+}
+
+/// Fetch and learn one source, returning a short status line.
+pub async fn learn_source(
+	agent: &mut Agent,
+	settings: &Settings,
+	#[cfg(feature = "feeds")] seen: &mut crate::modules::feeds::SeenStore,
+	source: &Source,
+) -> Result<String> {
+	match source.kind {
+		SourceKind::Page => {
+			let text = web::fetch_text(settings, &source.url).await?;
+			agent.summarize_and_learn(&source.url, &text).await?;
+			Ok("ok".to_string())
+		}
+		#[cfg(feature = "feeds")]
+		SourceKind::Feed => {
+			let n = crate::modules::feeds::learn_from_feed(agent, settings, seen, &source.url).await?;
+			Ok(format!("ok: {} new items", n))
+		}
+		#[cfg(not(feature = "feeds"))]
+		SourceKind::Feed => Err(anyhow!("feed sources require --features feeds")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const OPML: &str = r#"<?xml version="1.0"?>
+<opml version="2.0"><head><title>Subscriptions</title></head><body>
+	<outline text="News">
+		<outline text="Tech">
+			<outline type="rss" text="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml" category="/lang,News"/>
+		</outline>
+		<outline type="rss" text="BBC" title="BBC News" xmlUrl="https://bbc.example/rss"/>
+	</outline>
+	<outline type="link" text="Docs" url="https://docs.example/"/>
+	<outline type="rss" text="Again" xmlUrl="https://bbc.example/rss"/>
+</body></opml>"#;
+
+	fn summary(list: &SourceList) -> Vec<(String, SourceKind, Option<String>, Vec<String>)> {
+		list.sources.iter().map(|s| (s.url.clone(), s.kind, s.title.clone(), s.tags.clone())).collect()
+	}
+
+	#[test]
+	fn opml_import_and_export_round_trip() {
+		let mut list = SourceList::default();
+		assert_eq!(list.import_opml(OPML).unwrap(), 3);
+		let tags = |t: &[&str]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+		let expected = vec![
+			("https://blog.rust-lang.org/feed.xml".to_string(), SourceKind::Feed, Some("Rust Blog".to_string()), tags(&["News", "Tech", "lang"])),
+			("https://bbc.example/rss".to_string(), SourceKind::Feed, Some("BBC News".to_string()), tags(&["News"])),
+			("https://docs.example/".to_string(), SourceKind::Page, Some("Docs".to_string()), vec![]),
+		];
+		assert_eq!(summary(&list), expected);
+		// everything is a duplicate the second time
+		assert_eq!(list.import_opml(OPML).unwrap(), 0);
+
+		let mut copy = SourceList::default();
+		assert_eq!(copy.import_opml(&list.export_opml().unwrap()).unwrap(), 3);
+		assert_eq!(summary(&copy), expected);
+		assert!(list.import_opml("<not opml").is_err());
+	}
+
+	#[test]
+	fn save_status_keeps_edits_made_in_the_meantime() {
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join("sources.yaml");
+		let mut daemon = SourceList::read(path.clone()).unwrap();
+		daemon.add(Source::new("https://a.example/", SourceKind::Page)).unwrap();
+		daemon.add(Source::new("https://b.example/feed", SourceKind::Feed)).unwrap();
+		daemon.save().unwrap();
+
+		// `milyai sources` edits the file while the daemon is fetching
+		let mut edited = SourceList::read(path.clone()).unwrap();
+		edited.find_mut("https://a.example/").unwrap().enabled = false;
+		edited.find_mut("https://a.example/").unwrap().tags = vec!["edited".to_string()];
+		assert!(edited.remove("https://b.example/feed"));
+		edited.add(Source::new("https://c.example/", SourceKind::Page)).unwrap();
+		edited.save().unwrap();
+
+		let fetched = Utc::now();
+		for source in &mut daemon.sources {
+			source.last_fetch = Some(fetched);
+			source.last_status = Some("ok".to_string());
+		}
+		daemon.save_status().unwrap();
+
+		let now = SourceList::read(path).unwrap();
+		assert_eq!(now.sources.iter().map(|s| s.url.as_str()).collect::<Vec<_>>(), ["https://a.example/", "https://c.example/"]);
+		let a = &now.sources[0];
+		assert!(!a.enabled);
+		assert_eq!(a.tags, ["edited"]);
+		assert_eq!(a.last_status.as_deref(), Some("ok"));
+		assert_eq!(a.last_fetch, Some(fetched));
+		assert!(now.sources[1].last_status.is_none());
+	}
+}
\ No newline at end of file
@@ -1,136 +1,256 @@
 #![cfg(feature = "stt-vosk")]
 use anyhow::{anyhow, Result};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::collections::VecDeque;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-use vosk::{Model, Recognizer};
+use vosk::{DecodingState, Model, Recognizer};
 
-pub fn transcribe_once(model_path: Option<&std::path::Path>) -> Result<String> {
-	transcribe_for_secs(model_path, 4)
+use crate::modules::audio::{downmix, AudioFormat, AudioSource, Converter, CpalSource, MODEL_SAMPLE_RATE};
+use crate::modules::vad::{Vad, VadConfig, VadEvent};
+use crate::settings::Settings;
+
+/// A loaded Vosk model and the sample rate it expects audio at.
+pub struct SttModel {
+	model: Model,
+	sample_rate: u32,
+}
+
+impl SttModel {
+	pub fn load(model_path: Option<&Path>, sample_rate: Option<u32>) -> Result<Self> {
+		let model_path = model_path.ok_or_else(|| anyhow!("STT model path not set in config"))?;
+		if !model_path.exists() { return Err(anyhow!("Model not found: {:?}", model_path)); }
+		let model = Model::new(model_path.to_string_lossy()).ok_or_else(|| anyhow!("Failed to load Vosk model: {:?}", model_path))?;
+		Ok(Self { model, sample_rate: sample_rate.unwrap_or(MODEL_SAMPLE_RATE) })
+	}
+
+	#[cfg(feature = "speech")]
+	pub fn sample_rate(&self) -> u32 { self.sample_rate }
+
+	/// Recognizer restricted to `phrases` (plus "[unk]"), far cheaper and more precise for keyword spotting.
+	#[cfg(feature = "speech")]
+	pub fn grammar_recognizer(&self, phrases: &[String]) -> Result<Recognizer> {
+		let mut grammar: Vec<&str> = phrases.iter().map(|p| p.as_str()).collect();
+		grammar.push("[unk]");
+		Recognizer::new_with_grammar(&self.model, self.sample_rate as f32, &grammar).ok_or_else(|| anyhow!("Failed to create grammar recognizer"))
+	}
 }
 
-pub fn transcribe_for_secs(model_path: Option<&std::path::Path>, seconds: u64) -> Result<String> {
-	let model_path = model_path.ok_or_else(|| anyhow!("STT model path not set in config"))?;
-	if !Path::new(model_path).exists() { return Err(anyhow!("Model not found: {:?}", model_path)); }
-	let model = Model::new(model_path.to_str().unwrap())?;
-	let host = cpal::default_host();
-	let device = host.default_input_device().ok_or_else(|| anyhow!("No default input device"))?;
-	let config = device.default_input_config()?;
-	let sample_rate = config.sample_rate().0;
-	let mut recognizer = Recognizer::new(&model, sample_rate as f32)?;
-	let transcript: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
-	let transcript_clone = transcript.clone();
-
-	let stream = match config.sample_format() {
-		cpal::SampleFormat::F32 => device.build_input_stream(&config.into(), move |data: &[f32], _| {
-			let bytes: &[u8] = bytemuck::cast_slice(data);
-			let _ = recognizer.accept_waveform(bytes);
-			let part = recognizer.partial_result();
-			if !part.text().is_empty() { *transcript_clone.lock().unwrap() = part.text().to_string(); }
-		}, err_fn)?,
-		cpal::SampleFormat::I16 => device.build_input_stream(&config.into(), move |data: &[i16], _| {
-			let bytes: &[u8] = bytemuck::cast_slice(data);
-			let _ = recognizer.accept_waveform(bytes);
-			let part = recognizer.partial_result();
-			if !part.text().is_empty() { *transcript_clone.lock().unwrap() = part.text().to_string(); }
-		}, err_fn)?,
-		cpal::SampleFormat::U16 => device.build_input_stream(&config.into(), move |data: &[u16], _| {
-			let bytes: &[u8] = bytemuck::cast_slice(data);
-			let _ = recognizer.accept_waveform(bytes);
-			let part = recognizer.partial_result();
-			if !part.text().is_empty() { *transcript_clone.lock().unwrap() = part.text().to_string(); }
-		}, err_fn)?,
-		_ => return Err(anyhow!("Unsupported sample format")),
-	};
-	stream.play()?;
-	std::thread::sleep(std::time::Duration::from_secs(seconds));
-	drop(stream);
-	let final_res = recognizer.final_result();
-	let mut s = transcript.lock().unwrap().clone();
-	if !final_res.text().is_empty() { s = final_res.text().to_string(); }
-	Ok(s)
+pub fn load_model(settings: &Settings) -> Result<SttModel> {
+	SttModel::load(settings.stt_model_path.as_deref(), settings.stt_sample_rate)
 }
 
-/// Listen until trailing silence exceeds `silence_ms` or `max_secs` reached, then return final transcript.
-pub fn transcribe_until_silence(
-	model_path: Option<&std::path::Path>,
-	silence_ms: u64,
-	max_secs: u64,
-) -> Result<String> {
-	let model_path = model_path.ok_or_else(|| anyhow!("STT model path not set in config"))?;
-	if !Path::new(model_path).exists() { return Err(anyhow!("Model not found: {:?}", model_path)); }
-	let model = Model::new(model_path.to_str().unwrap())?;
-	let host = cpal::default_host();
-	let device = host.default_input_device().ok_or_else(|| anyhow!("No default input device"))?;
-	let config = device.default_input_config()?;
-	let sample_rate = config.sample_rate().0 as usize;
-	let mut recognizer = Recognizer::new(&model, sample_rate as f32)?;
-	let transcript: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
-	let transcript_clone = transcript.clone();
-	let last_sound_time = Arc::new(Mutex::new(Instant::now()));
-	let last_sound_time_cl = last_sound_time.clone();
-
-	// Simple energy-based VAD
-	let energy_threshold = 0.003; // adjust if needed
-	let frame_ms = 20usize;
-	let frame_len = sample_rate * frame_ms / 1000;
-
-	let start_time = Instant::now();
-	let stream = match config.sample_format() {
-		cpal::SampleFormat::F32 => device.build_input_stream(&config.into(), move |data: &[f32], _| {
-			let bytes: &[u8] = bytemuck::cast_slice(data);
-			let _ = recognizer.accept_waveform(bytes);
-			// VAD: compute mean abs over frames
-			for chunk in data.chunks(frame_len.max(1)) {
-				let energy = chunk.iter().map(|s| s.abs()).sum::<f32>() / (chunk.len().max(1) as f32);
-				if energy > energy_threshold {
-					*last_sound_time_cl.lock().unwrap() = Instant::now();
+/// Listen on the microphone for one utterance delimited by the VAD, then return its transcript.
+pub fn transcribe_utterance(settings: &Settings) -> Result<String> {
+	let model = load_model(settings)?;
+	let mut source = CpalSource::from_settings(settings)?;
+	transcribe_utterance_from(&model, &mut source, &VadConfig::from_settings(settings))
+}
+
+/// Transcribe a source until it ends or `max_secs` of audio has been consumed.
+pub fn transcribe_source(model: &SttModel, source: &mut dyn AudioSource, max_secs: Option<f32>) -> Result<String> {
+	let format = source.format();
+	let mut session = Session::new(model, format)?;
+	let max_frames = max_secs.map(|s| (s * format.sample_rate as f32) as usize);
+	let mut frames = 0usize;
+	while let Some(block) = source.next_block()? {
+		session.feed(&block);
+		frames += block.len() / format.channels.max(1) as usize;
+		if max_frames.is_some_and(|m| frames >= m) { break; }
+	}
+	Ok(session.finish())
+}
+
+/// Wait for speech, then transcribe until trailing silence, the utterance limit, or the end of the source.
+/// Audio before speech onset is only kept as pre-roll; durations are counted in samples, so file and
+/// in-memory sources behave like the microphone.
+pub fn transcribe_utterance_from(model: &SttModel, source: &mut dyn AudioSource, cfg: &VadConfig) -> Result<String> {
+	let format = source.format();
+	let channels = format.channels.max(1) as usize;
+	let frame_len = cfg.frame_len(format.sample_rate) * channels;
+	let frame_ms = cfg.frame_ms.max(1);
+	let pre_roll_frames = ((cfg.pre_roll_ms + cfg.min_speech_ms) / frame_ms).max(1) as usize;
+
+	let mut session = Session::new(model, format)?;
+	let mut vad = Vad::new(cfg.clone());
+	let mut pre_roll: VecDeque<Vec<f32>> = VecDeque::with_capacity(pre_roll_frames + 1);
+	let mut pending: Vec<f32> = Vec::new();
+	'outer: while let Some(block) = source.next_block()? {
+		pending.extend_from_slice(&block);
+		let whole = pending.len() - pending.len() % frame_len;
+		let frames: Vec<f32> = pending.drain(..whole).collect();
+		for frame in frames.chunks(frame_len) {
+			let event = vad.process_frame(&downmix(frame, channels));
+			if vad.in_speech() || event == VadEvent::SpeechEnd {
+				if event == VadEvent::SpeechStart {
+					for f in pre_roll.drain(..) { session.feed(&f); }
 				}
+				session.feed(frame);
+				// the VAD also ends speech at the utterance limit
+				if event == VadEvent::SpeechEnd { break 'outer; }
+			} else {
+				pre_roll.push_back(frame.to_vec());
+				if pre_roll.len() > pre_roll_frames { pre_roll.pop_front(); }
 			}
-			let part = recognizer.partial_result();
-			if !part.text().is_empty() { *transcript_clone.lock().unwrap() = part.text().to_string(); }
-		}, err_fn)?,
-		cpal::SampleFormat::I16 => device.build_input_stream(&config.into(), move |data: &[i16], _| {
-			let bytes: &[u8] = bytemuck::cast_slice(data);
-			let _ = recognizer.accept_waveform(bytes);
-			for chunk in data.chunks(frame_len.max(1)) {
-				let energy = chunk.iter().map(|s| (i16::abs(*s) as f32) / (i16::MAX as f32)).sum::<f32>() / (chunk.len().max(1) as f32);
-				if energy > energy_threshold {
-					*last_sound_time_cl.lock().unwrap() = Instant::now();
-				}
+		}
+	}
+	Ok(session.finish())
+}
+
+/// An open microphone delivering VAD-sized mono frames at the model rate, with the VAD run over
+/// every frame so it stays calibrated across turns.
+#[cfg(feature = "speech")]
+pub struct Mic {
+	source: CpalSource,
+	converter: Converter,
+	vad: Vad,
+	pending: Vec<i16>,
+	sample_rate: u32,
+}
+
+#[cfg(feature = "speech")]
+impl Mic {
+	pub fn open(settings: &Settings, model: &SttModel) -> Result<Self> {
+		let source = CpalSource::from_settings(settings)?;
+		let converter = Converter::new(source.format(), model.sample_rate);
+		Ok(Self { source, converter, vad: Vad::new(VadConfig::from_settings(settings)), pending: Vec::new(), sample_rate: model.sample_rate })
+	}
+
+	pub fn vad(&self) -> &Vad { &self.vad }
+
+	pub fn frame_ms(&self) -> u32 { self.vad.config().frame_ms.max(1) }
+
+	/// Next frame and the VAD's verdict on it.
+	pub fn next_frame(&mut self) -> Result<(Vec<i16>, VadEvent)> {
+		let frame_len = self.vad.config().frame_len(self.sample_rate);
+		while self.pending.len() < frame_len {
+			let block = self.source.next_block()?.ok_or_else(|| anyhow!("Microphone stream ended"))?;
+			let pcm = self.converter.process(&block);
+			self.pending.extend(pcm);
+		}
+		Ok(self.take_frame(frame_len))
+	}
+
+	/// Like `next_frame`, but None instead of waiting when a full frame has not been captured yet.
+	#[cfg(feature = "speech")]
+	pub fn try_next_frame(&mut self) -> Result<Option<(Vec<i16>, VadEvent)>> {
+		let frame_len = self.vad.config().frame_len(self.sample_rate);
+		while self.pending.len() < frame_len {
+			let Some(block) = self.source.try_next_block()? else { return Ok(None) };
+			let pcm = self.converter.process(&block);
+			self.pending.extend(pcm);
+		}
+		Ok(Some(self.take_frame(frame_len)))
+	}
+
+	fn take_frame(&mut self, frame_len: usize) -> (Vec<i16>, VadEvent) {
+		let frame: Vec<i16> = self.pending.drain(..frame_len).collect();
+		let event = self.vad.process_frame(&to_f32(&frame));
+		(frame, event)
+	}
+
+	/// Forget audio captured while we were busy and return the VAD to silence.
+	pub fn discard_buffered(&mut self) {
+		self.source.discard_buffered();
+		self.pending.clear();
+		self.vad.reset();
+	}
+
+	/// Wait for speech and transcribe it until trailing silence. `initial` is speech already heard
+	/// (e.g. the start of a barge-in), in which case the utterance counts as started.
+	pub fn utterance(&mut self, model: &SttModel, initial: &[i16]) -> Result<String> {
+		let mut session = Session::new(model, AudioFormat { sample_rate: self.sample_rate, channels: 1 })?;
+		if initial.is_empty() {
+			let cfg = self.vad.config();
+			let pre_roll_frames = ((cfg.pre_roll_ms + cfg.min_speech_ms) / cfg.frame_ms.max(1)).max(1) as usize;
+			let mut pre_roll: VecDeque<Vec<i16>> = VecDeque::with_capacity(pre_roll_frames + 1);
+			loop {
+				let (frame, event) = self.next_frame()?;
+				pre_roll.push_back(frame);
+				if pre_roll.len() > pre_roll_frames { pre_roll.pop_front(); }
+				if event == VadEvent::SpeechStart { break; }
 			}
-			let part = recognizer.partial_result();
-			if !part.text().is_empty() { *transcript_clone.lock().unwrap() = part.text().to_string(); }
-		}, err_fn)?,
-		cpal::SampleFormat::U16 => device.build_input_stream(&config.into(), move |data: &[u16], _| {
-			let bytes: &[u8] = bytemuck::cast_slice(data);
-			let _ = recognizer.accept_waveform(bytes);
-			for chunk in data.chunks(frame_len.max(1)) {
-				let energy = chunk.iter().map(|s| ((*s as i32 - u16::MAX as i32/2).abs() as f32) / (i16::MAX as f32)).sum::<f32>() / (chunk.len().max(1) as f32);
-				if energy > energy_threshold {
-					*last_sound_time_cl.lock().unwrap() = Instant::now();
-				}
+			for f in pre_roll { session.accept(&f); }
+		} else {
+			session.accept(initial);
+		}
+		let silence_ms = self.vad.config().silence_ms;
+		self.capture(session, silence_ms)
+	}
+
+	/// Feed the live stream into `session` until the utterance ends, nothing is said for `wait_ms`,
+	/// or the utterance limit is reached.
+	pub fn capture(&mut self, mut session: Session, wait_ms: u32) -> Result<String> {
+		let frame_ms = self.frame_ms();
+		let max_ms = self.vad.config().max_utterance_ms;
+		let (mut quiet, mut total) = (0u32, 0u32);
+		loop {
+			let (frame, event) = self.next_frame()?;
+			session.accept(&frame);
+			total += frame_ms;
+			quiet = if self.vad.in_speech() { 0 } else { quiet + frame_ms };
+			if event == VadEvent::SpeechEnd || quiet >= wait_ms || total >= max_ms { break; }
+		}
+		Ok(session.finish())
+	}
+}
+
+#[cfg(feature = "speech")]
+pub fn to_f32(pcm: &[i16]) -> Vec<f32> {
+	pcm.iter().map(|s| *s as f32 / 32768.0).collect()
+}
+
+/// One recognizer pass, collecting utterances Vosk finalizes mid-stream.
+pub struct Session {
+	recognizer: Recognizer,
+	converter: Converter,
+	text: String,
+	partial: String,
+}
+
+impl Session {
+	pub fn new(model: &SttModel, input: AudioFormat) -> Result<Self> {
+		let recognizer = Recognizer::new(&model.model, model.sample_rate as f32).ok_or_else(|| anyhow!("Failed to create recognizer"))?;
+		Ok(Self { recognizer, converter: Converter::new(input, model.sample_rate), text: String::new(), partial: String::new() })
+	}
+
+	/// Feed interleaved samples in the session's input format.
+	pub fn feed(&mut self, block: &[f32]) {
+		let pcm = self.converter.process(block);
+		self.accept(&pcm);
+	}
+
+	/// Feed mono i16 audio already at the model rate.
+	pub fn accept(&mut self, pcm: &[i16]) {
+		if pcm.is_empty() { return; }
+		match self.recognizer.accept_waveform(pcm) {
+			Ok(DecodingState::Finalized) => {
+				let done = result_text(self.recognizer.result());
+				push_words(&mut self.text, &done);
+				self.partial.clear();
 			}
-			let part = recognizer.partial_result();
-			if !part.text().is_empty() { *transcript_clone.lock().unwrap() = part.text().to_string(); }
-		}, err_fn)?,
-		_ => return Err(anyhow!("Unsupported sample format")),
-	};
-	stream.play()?;
-	loop {
-		let since = Instant::now().duration_since(*last_sound_time.lock().unwrap());
-		if since >= Duration::from_millis(silence_ms) { break; }
-		if start_time.elapsed() >= Duration::from_secs(max_secs) { break; }
-		std::thread::sleep(Duration::from_millis(20));
-	}
-	drop(stream);
-	let final_res = recognizer.final_result();
-	let mut s = transcript.lock().unwrap().clone();
-	if !final_res.text().is_empty() { s = final_res.text().to_string(); }
-	Ok(s)
+			Ok(_) => self.partial = self.recognizer.partial_result().partial.to_string(),
+			Err(e) => eprintln!("Recognizer rejected audio: {}", e),
+		}
+	}
+
+	pub fn finish(mut self) -> String {
+		let tail = self.converter.flush();
+		self.accept(&tail);
+		let last = result_text(self.recognizer.final_result());
+		push_words(&mut self.text, if last.is_empty() { &self.partial } else { &last });
+		self.text
+	}
+}
+
+pub fn result_text(result: vosk::CompleteResult) -> String {
+	match result {
+		vosk::CompleteResult::Single(r) => r.text.to_string(),
+		vosk::CompleteResult::Multiple(m) => m.alternatives.first().map(|a| a.text.to_string()).unwrap_or_default(),
+	}
 }
 
-fn err_fn(err: cpal::StreamError) {
-	eprintln!("Audio stream error: {}", err);
-} 
\ No newline at end of file
+fn push_words(buf: &mut String, words: &str) {
+	let words = words.trim();
+	if words.is_empty() { return; }
+	if !buf.is_empty() { buf.push(' '); }
+	buf.push_str(words);
+}
\ No newline at end of file
@@ -1,19 +1,391 @@
+#![cfg(feature = "speech")]
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 #[cfg(feature = "tts")]
-use anyhow::Result;
+use std::time::Instant;
+
+use crate::settings::Settings;
+
+/// Rough speaking speed, to guess playback length on engines that cannot report it
+#[cfg(feature = "tts")]
+const CHARS_PER_SEC: f32 = 14.0;
+/// Time an engine may take to start talking before we assume it finished
+#[cfg(feature = "tts")]
+const START_GRACE: Duration = Duration::from_millis(1000);
+/// Native rate and volume ranges are divided into this many steps for `adjust`
+#[cfg(feature = "tts")]
+const NATIVE_STEPS: f32 = 10.0;
+
+/// A speech engine, driven one sentence at a time by [`SpeechWorker`].
+pub trait TtsBackend {
+	/// Start speaking `text`, replacing anything still playing, and return at once.
+	fn speak(&mut self, text: &str) -> Result<()>;
+	fn is_speaking(&mut self) -> bool;
+	fn stop(&mut self);
+	/// Speak faster/slower and louder/quieter by a number of steps, for the rest of the session.
+	fn adjust(&mut self, rate_steps: i32, volume_steps: i32);
+	/// Switch to a voice for a language code such as "en", if the engine has one.
+	fn set_language(&mut self, _language: &str) -> Result<()> {
+		Err(anyhow!("This TTS backend cannot switch language"))
+	}
+	/// Render `text` as WAV without playing it.
+	fn render(&mut self, _text: &str) -> Result<Vec<u8>> {
+		Err(anyhow!("This TTS backend cannot render audio files; use tts_backend: piper or espeak"))
+	}
+}
+
+/// The backend chosen by `tts_backend`: "native" (OS speech engine, the default when built with
+/// the `tts` feature), "piper" or "espeak" (the default without it).
+pub fn backend(settings: &Settings) -> Result<Box<dyn TtsBackend>> {
+	let default = if cfg!(feature = "tts") { "native" } else { "espeak" };
+	match settings.tts_backend.as_deref().unwrap_or(default) {
+		#[cfg(feature = "tts")]
+		"native" => Ok(Box::new(NativeBackend::new(settings)?)),
+		#[cfg(not(feature = "tts"))]
+		"native" => Err(anyhow!("tts_backend 'native' requires --features tts")),
+		#[cfg(feature = "tts-process")]
+		"piper" | "espeak" | "espeak-ng" => Ok(Box::new(crate::modules::tts_process::ProcessBackend::new(settings)?)),
+		#[cfg(not(feature = "tts-process"))]
+		other @ ("piper" | "espeak" | "espeak-ng") => Err(anyhow!("tts_backend '{}' requires --features tts-process", other)),
+		other => Err(anyhow!("Unknown tts_backend: {}", other)),
+	}
+}
+
+/// The OS speech engine via the `tts` crate (speech-dispatcher, SAPI, AVFoundation...).
+#[cfg(feature = "tts")]
+pub struct NativeBackend {
+	engine: tts::Tts,
+	started: Instant,
+	estimate: Duration,
+	heard: bool,
+}
 
 #[cfg(feature = "tts")]
-pub fn speak(settings: &crate::settings::Settings, text: &str) -> Result<()> {
-	let mut engine = tts::Tts::default()?;
-	if let Some(v) = &settings.tts_voice {
-		if let Ok(voices) = engine.voices() {
-			if let Some(found) = voices.into_iter().find(|vv| vv.name.eq_ignore_ascii_case(v)) {
-				let _ = engine.set_voice(&found);
+impl NativeBackend {
+	pub fn new(settings: &Settings) -> Result<Self> {
+		let mut engine = tts::Tts::default()?;
+		if let Some(v) = &settings.tts_voice {
+			if let Ok(voices) = engine.voices() {
+				if let Some(found) = voices.into_iter().find(|vv| vv.name.eq_ignore_ascii_case(v)) {
+					let _ = engine.set_voice(&found);
+				}
 			}
 		}
+		if let Some(rate) = settings.tts_rate { let _ = engine.set_rate(rate); }
+		if let Some(pitch) = settings.tts_pitch { let _ = engine.set_pitch(pitch); }
+		if let Some(vol) = settings.tts_volume { let _ = engine.set_volume(vol); }
+		Ok(Self { engine, started: Instant::now(), estimate: Duration::ZERO, heard: false })
 	}
-	if let Some(rate) = settings.tts_rate { let _ = engine.set_rate(rate); }
-	if let Some(pitch) = settings.tts_pitch { let _ = engine.set_pitch(pitch); }
-	if let Some(vol) = settings.tts_volume { let _ = engine.set_volume(vol); }
-	engine.speak(text, false)?;
+}
+
+#[cfg(feature = "tts")]
+impl TtsBackend for NativeBackend {
+	fn speak(&mut self, text: &str) -> Result<()> {
+		self.engine.speak(text, true)?;
+		self.started = Instant::now();
+		self.estimate = Duration::from_secs_f32(text.chars().count() as f32 / CHARS_PER_SEC);
+		self.heard = false;
+		Ok(())
+	}
+
+	fn is_speaking(&mut self) -> bool {
+		match self.engine.is_speaking() {
+			Ok(true) => { self.heard = true; true }
+			Ok(false) => !self.heard && self.started.elapsed() < START_GRACE,
+			Err(_) => self.started.elapsed() < self.estimate,
+		}
+	}
+
+	fn stop(&mut self) {
+		let _ = self.engine.stop();
+		self.estimate = Duration::ZERO;
+		self.heard = true;
+	}
+
+	fn adjust(&mut self, rate_steps: i32, volume_steps: i32) {
+		let e = &mut self.engine;
+		if rate_steps != 0 {
+			let (min, max) = (e.min_rate(), e.max_rate());
+			let rate = e.get_rate().unwrap_or_else(|_| e.normal_rate()) + (max - min) / NATIVE_STEPS * rate_steps as f32;
+			let _ = e.set_rate(rate.clamp(min, max));
+		}
+		if volume_steps != 0 {
+			let (min, max) = (e.min_volume(), e.max_volume());
+			let volume = e.get_volume().unwrap_or_else(|_| e.normal_volume()) + (max - min) / NATIVE_STEPS * volume_steps as f32;
+			let _ = e.set_volume(volume.clamp(min, max));
+		}
+	}
+
+	fn set_language(&mut self, language: &str) -> Result<()> {
+		let voice = self.engine.voices()?.into_iter()
+			.find(|v| v.language().to_string().to_lowercase().starts_with(language))
+			.ok_or_else(|| anyhow!("No installed voice for language '{}'", language))?;
+		self.engine.set_voice(&voice)?;
+		Ok(())
+	}
+}
+
+pub fn speak(settings: &Settings, text: &str) -> Result<()> {
+	let speech = SpeechWorker::spawn(settings)?;
+	speech.say(text);
+	speech.wait();
+	Ok(())
+}
+
+/// Render `text` to a WAV file without playing it.
+pub fn render_to_file(settings: &Settings, text: &str, out: &Path) -> Result<()> {
+	let wav = backend(settings)?.render(text)?;
+	std::fs::write(out, wav)?;
 	Ok(())
-} 
\ No newline at end of file
+}
+
+enum Command {
+	Text(String),
+	Finish,
+	Cancel,
+	Adjust(i32, i32),
+	Language(String),
+}
+
+/// A speech thread with a queue. Reply text can be pushed piecemeal while it is still being
+/// generated; each complete sentence is spoken as soon as the one before it ends.
+pub struct SpeechWorker {
+	tx: mpsc::Sender<Command>,
+	/// Commands sent but not yet handled by the thread
+	outstanding: Arc<AtomicUsize>,
+	/// IDLE, QUEUED or PLAYING
+	state: Arc<AtomicU8>,
+}
+
+const IDLE: u8 = 0;
+/// Text waiting for a sentence end or for the previous sentence to finish
+const QUEUED: u8 = 1;
+const PLAYING: u8 = 2;
+
+impl SpeechWorker {
+	pub fn spawn(settings: &Settings) -> Result<Self> {
+		let (tx, rx) = mpsc::channel();
+		let (ready_tx, ready_rx) = mpsc::channel();
+		let outstanding = Arc::new(AtomicUsize::new(0));
+		let state = Arc::new(AtomicU8::new(IDLE));
+		let settings = settings.clone();
+		let (o, st) = (outstanding.clone(), state.clone());
+		std::thread::spawn(move || {
+			// the engine lives on this thread for the whole session
+			let backend = match backend(&settings) {
+				Ok(b) => { let _ = ready_tx.send(Ok(())); b }
+				Err(e) => { let _ = ready_tx.send(Err(e)); return; }
+			};
+			run_worker(backend, rx, o, st);
+		});
+		ready_rx.recv().map_err(|_| anyhow!("Speech thread exited during startup"))??;
+		Ok(Self { tx, outstanding, state })
+	}
+
+	/// Queue more reply text; complete sentences start playing right away.
+	pub fn push(&self, text: &str) {
+		self.send(Command::Text(text.to_string()));
+	}
+
+	/// The reply is complete; speak whatever is left without waiting for a sentence end.
+	pub fn finish(&self) {
+		self.send(Command::Finish);
+	}
+
+	pub fn say(&self, text: &str) {
+		self.push(text);
+		self.finish();
+	}
+
+	/// Stop speaking and drop everything queued.
+	pub fn cancel(&self) {
+		self.send(Command::Cancel);
+	}
+
+	/// Change speed and volume by steps for the rest of the session (negative is slower/quieter).
+	// only voice commands change these so far
+	#[cfg_attr(not(feature = "stt-vosk"), allow(dead_code))]
+	pub fn adjust(&self, rate_steps: i32, volume_steps: i32) {
+		self.send(Command::Adjust(rate_steps, volume_steps));
+	}
+
+	#[cfg_attr(not(feature = "stt-vosk"), allow(dead_code))]
+	pub fn set_language(&self, language: &str) {
+		self.send(Command::Language(language.to_string()));
+	}
+
+	/// True while text is queued, waiting for a sentence end, or playing.
+	pub fn is_busy(&self) -> bool {
+		self.outstanding.load(Ordering::SeqCst) > 0 || self.state.load(Ordering::SeqCst) != IDLE
+	}
+
+	/// True only while audio is actually coming out, not while the first sentence is still awaited.
+	#[cfg(feature = "stt-vosk")]
+	pub fn is_playing(&self) -> bool {
+		self.state.load(Ordering::SeqCst) == PLAYING
+	}
+
+	pub fn wait(&self) {
+		while self.is_busy() { std::thread::sleep(Duration::from_millis(50)); }
+	}
+
+	fn send(&self, cmd: Command) {
+		self.outstanding.fetch_add(1, Ordering::SeqCst);
+		if self.tx.send(cmd).is_err() { self.outstanding.fetch_sub(1, Ordering::SeqCst); }
+	}
+}
+
+fn run_worker(mut backend: Box<dyn TtsBackend>, rx: mpsc::Receiver<Command>, outstanding: Arc<AtomicUsize>, state: Arc<AtomicU8>) {
+	let mut pending = String::new();
+	let mut queue: VecDeque<String> = VecDeque::new();
+	let mut speaking = false;
+	loop {
+		let cmd = match rx.recv_timeout(Duration::from_millis(30)) {
+			Ok(cmd) => Some(cmd),
+			Err(mpsc::RecvTimeoutError::Timeout) => None,
+			Err(mpsc::RecvTimeoutError::Disconnected) => {
+				if speaking { backend.stop(); }
+				return;
+			}
+		};
+		match &cmd {
+			Some(Command::Text(t)) => {
+				pending.push_str(t);
+				queue.extend(take_sentences(&mut pending));
+			}
+			Some(Command::Finish) => {
+				let rest = pending.trim();
+				if !rest.is_empty() { queue.push_back(rest.to_string()); }
+				pending.clear();
+			}
+			Some(Command::Cancel) => {
+				pending.clear();
+				queue.clear();
+				if speaking { backend.stop(); }
+				speaking = false;
+			}
+			Some(Command::Adjust(rate, volume)) => backend.adjust(*rate, *volume),
+			Some(Command::Language(lang)) => {
+				if let Err(e) = backend.set_language(lang) { tracing::warn!("{}", e); }
+			}
+			None => {}
+		}
+		if speaking && !backend.is_speaking() { speaking = false; }
+		if !speaking {
+			if let Some(sentence) = queue.pop_front() {
+				match backend.speak(&sentence) {
+					Ok(()) => speaking = true,
+					Err(e) => tracing::warn!("TTS failed: {}", e),
+				}
+			}
+		}
+		let now = if speaking { PLAYING } else if !queue.is_empty() || !pending.is_empty() { QUEUED } else { IDLE };
+		state.store(now, Ordering::SeqCst);
+		if cmd.is_some() { outstanding.fetch_sub(1, Ordering::SeqCst); }
+	}
+}
+
+/// Split complete sentences off the front of `buf`, leaving the unfinished tail. A sentence ends at a
+/// newline or at . ! ? … followed by whitespace, so "3.5" and a final "." that may still continue stay put.
+pub fn take_sentences(buf: &mut String) -> Vec<String> {
+	let mut out = Vec::new();
+	let mut start = 0;
+	let mut chars = buf.char_indices().peekable();
+	while let Some((pos, c)) = chars.next() {
+		let end = match c {
+			'\n' => true,
+			'.' | '!' | '?' | '…' => chars.peek().is_some_and(|(_, next)| next.is_whitespace()),
+			_ => false,
+		};
+		if end {
+			let cut = pos + c.len_utf8();
+			let sentence = buf[start..cut].trim();
+			if !sentence.is_empty() { out.push(sentence.to_string()); }
+			start = cut;
+		}
+	}
+	buf.drain(..start);
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn take(buf: &mut String, more: &str) -> Vec<String> {
+		buf.push_str(more);
+		take_sentences(buf)
+	}
+
+	#[test]
+	fn take_sentences_waits_for_whitespace_after_the_stop() {
+		let mut buf = String::new();
+		assert_eq!(take(&mut buf, "Harganya 3.5 juta. Murah"), ["Harganya 3.5 juta."]);
+		assert_eq!(buf, " Murah");
+		// the final "." may still be followed by more of the number or an ellipsis
+		assert!(take(&mut buf, " sekali.").is_empty());
+		assert_eq!(buf, " Murah sekali.");
+		assert_eq!(take(&mut buf, " Lagi"), ["Murah sekali."]);
+		assert_eq!(buf, " Lagi");
+		assert_eq!(take(&mut buf, "?! Ya"), ["Lagi?!"]);
+	}
+
+	#[test]
+	fn take_sentences_splits_at_ellipsis_and_newlines() {
+		let mut buf = String::new();
+		assert_eq!(take(&mut buf, "Tunggu… ya"), ["Tunggu…"]);
+		assert_eq!(take(&mut buf, "\n\n- satu\n- dua\n"), ["ya", "- satu", "- dua"]);
+		assert_eq!(buf, "");
+	}
+
+	#[test]
+	fn take_sentences_handles_multibyte_text() {
+		let mut buf = String::new();
+		assert_eq!(take(&mut buf, "日本語です。 Café? Ünïcödé! 😀 rest"), ["日本語です。 Café?", "Ünïcödé!"]);
+		assert_eq!(buf, " 😀 rest");
+	}
+
+	fn backend_error(settings: &Settings) -> String {
+		match backend(settings) {
+			Ok(_) => panic!("expected an error"),
+			Err(e) => e.to_string(),
+		}
+	}
+
+	fn with_backend(name: &str) -> Settings {
+		Settings { tts_backend: Some(name.to_string()), ..Default::default() }
+	}
+
+	#[test]
+	fn backend_rejects_unknown_names() {
+		assert_eq!(backend_error(&with_backend("festival")), "Unknown tts_backend: festival");
+	}
+
+	#[cfg(not(feature = "tts"))]
+	#[test]
+	fn backend_native_needs_the_tts_feature() {
+		assert!(backend_error(&with_backend("native")).contains("requires --features tts"));
+	}
+
+	#[cfg(feature = "tts-process")]
+	#[test]
+	fn backend_selects_process_engines() {
+		assert!(backend(&with_backend("espeak")).is_ok());
+		assert!(backend(&with_backend("espeak-ng")).is_ok());
+		assert!(backend_error(&with_backend("piper")).contains("needs tts_model_path"));
+		let piper = Settings { tts_model_path: Some("voice.onnx".into()), ..with_backend("piper") };
+		assert!(backend(&piper).is_ok());
+		#[cfg(not(feature = "tts"))]
+		assert!(backend(&Settings::default()).is_ok());
+	}
+
+	#[cfg(not(feature = "tts-process"))]
+	#[test]
+	fn backend_process_engines_need_the_tts_process_feature() {
+		assert_eq!(backend_error(&with_backend("piper")), "tts_backend 'piper' requires --features tts-process");
+	}
+}
\ No newline at end of file
@@ -0,0 +1,249 @@
+#![cfg(feature = "tts-process")]
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use parking_lot::Mutex;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use crate::modules::tts::TtsBackend;
+use crate::settings::Settings;
+
+/// espeak-ng's default speed in words per minute
+const ESPEAK_WPM: f32 = 175.0;
+/// Factor applied per `adjust` step
+const RATE_STEP: f32 = 1.15;
+const VOLUME_STEP: f32 = 1.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Engine {
+	Piper,
+	Espeak,
+}
+
+/// Offline synthesis by running a Piper or espeak-ng binary, which renders a WAV that is played
+/// through cpal. `tts_rate` and `tts_volume` are factors here (1.0 = normal).
+pub struct ProcessBackend {
+	engine: Engine,
+	command: PathBuf,
+	model: Option<PathBuf>,
+	voice: Option<String>,
+	rate: f32,
+	volume: f32,
+	player: Option<Player>,
+}
+
+impl ProcessBackend {
+	pub fn new(settings: &Settings) -> Result<Self> {
+		let engine = match settings.tts_backend.as_deref() {
+			Some("piper") => Engine::Piper,
+			_ => Engine::Espeak,
+		};
+		let command = settings.tts_command.clone().unwrap_or_else(|| match engine {
+			Engine::Piper => "piper".into(),
+			Engine::Espeak => "espeak-ng".into(),
+		});
+		if engine == Engine::Piper && settings.tts_model_path.is_none() {
+			return Err(anyhow!("tts_backend: piper needs tts_model_path (a .onnx voice)"));
+		}
+		Ok(Self {
+			engine,
+			command,
+			model: settings.tts_model_path.clone(),
+			voice: settings.tts_voice.clone(),
+			rate: settings.tts_rate.filter(|r| *r > 0.0).unwrap_or(1.0),
+			volume: settings.tts_volume.unwrap_or(1.0),
+			player: None,
+		})
+	}
+
+	/// Run the engine and return the WAV it produced.
+	pub fn synthesize(&self, text: &str) -> Result<Vec<u8>> {
+		match self.engine {
+			Engine::Piper => {
+				let model = self.model.as_ref().ok_or_else(|| anyhow!("tts_model_path not set"))?;
+				// a private file of our own, removed when dropped
+				let out = tempfile::Builder::new().prefix("milyai-tts-").suffix(".wav").tempfile()?;
+				let mut cmd = Command::new(&self.command);
+				cmd.arg("--model").arg(model)
+					.arg("--length_scale").arg(format!("{:.2}", 1.0 / self.rate))
+					.arg("--output_file").arg(out.path());
+				run(&mut cmd, text)?;
+				Ok(std::fs::read(out.path())?)
+			}
+			Engine::Espeak => {
+				let mut cmd = Command::new(&self.command);
+				cmd.arg("--stdout").arg("--stdin").arg("-s").arg(((ESPEAK_WPM * self.rate).round() as u32).to_string());
+				if let Some(v) = &self.voice { cmd.arg("-v").arg(v); }
+				run(&mut cmd, text)
+			}
+		}
+	}
+}
+
+impl TtsBackend for ProcessBackend {
+	fn speak(&mut self, text: &str) -> Result<()> {
+		self.player = None;
+		let wav = self.synthesize(text)?;
+		self.player = Some(Player::play(&wav, self.volume)?);
+		Ok(())
+	}
+
+	fn is_speaking(&mut self) -> bool {
+		self.player.as_ref().is_some_and(|p| p.is_playing())
+	}
+
+	fn stop(&mut self) {
+		self.player = None;
+	}
+
+	fn adjust(&mut self, rate_steps: i32, volume_steps: i32) {
+		self.rate = (self.rate * RATE_STEP.powi(rate_steps)).clamp(0.5, 2.5);
+		self.volume = (self.volume * VOLUME_STEP.powi(volume_steps)).clamp(0.1, 2.0);
+	}
+
+	fn set_language(&mut self, language: &str) -> Result<()> {
+		match self.engine {
+			// espeak-ng voices are named by language code
+			Engine::Espeak => { self.voice = Some(language.to_string()); Ok(()) }
+			Engine::Piper => Err(anyhow!("Piper voices are single-language; set tts_model_path to a {} voice", language)),
+		}
+	}
+
+	fn render(&mut self, text: &str) -> Result<Vec<u8>> {
+		self.synthesize(text)
+	}
+}
+
+/// Feed `input` on stdin and collect stdout, failing with the tool's stderr. Stdin is written on its
+/// own thread so a tool that fills its stdout pipe before reading all input cannot deadlock us.
+fn run(cmd: &mut Command, input: &str) -> Result<Vec<u8>> {
+	let program = cmd.get_program().to_string_lossy().to_string();
+	let mut child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+		.map_err(|e| anyhow!("Failed to start {}: {}", program, e))?;
+	let writer = child.stdin.take().map(|mut stdin| {
+		let input = input.to_string();
+		std::thread::spawn(move || stdin.write_all(input.as_bytes()))
+	});
+	let out = child.wait_with_output()?;
+	if let Some(writer) = writer {
+		// a tool may exit without reading everything; its exit status tells what went wrong
+		if let Ok(Err(e)) = writer.join() {
+			if out.status.success() { return Err(anyhow!("Writing to {} failed: {}", program, e)); }
+		}
+	}
+	if !out.status.success() {
+		return Err(anyhow!("{} failed: {}", program, String::from_utf8_lossy(&out.stderr).trim()));
+	}
+	Ok(out.stdout)
+}
+
+struct PlayState {
+	samples: Vec<f32>,
+	pos: usize,
+}
+
+/// One WAV playing on the default output device; dropping it stops playback.
+struct Player {
+	_stream: cpal::Stream,
+	state: Arc<Mutex<PlayState>>,
+}
+
+impl Player {
+	fn play(wav: &[u8], gain: f32) -> Result<Self> {
+		let reader = hound::WavReader::new(Cursor::new(wav))?;
+		let spec = reader.spec();
+		let samples: Vec<f32> = match spec.sample_format {
+			hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+			hound::SampleFormat::Int => {
+				let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+				reader.into_samples::<i32>().map(|s| s.map(|v| v as f32 / scale)).collect::<Result<_, _>>()?
+			}
+		};
+		let channels = spec.channels.max(1) as usize;
+		let mono: Vec<f32> = samples.chunks(channels).map(|f| f.iter().sum::<f32>() / f.len() as f32).collect();
+
+		let device = cpal::default_host().default_output_device().ok_or_else(|| anyhow!("No default output device"))?;
+		let config = device.default_output_config()?;
+		let out_rate = config.sample_rate().0;
+		let samples = resample_linear(&mono, spec.sample_rate, out_rate).into_iter().map(|s| (s * gain).clamp(-1.0, 1.0)).collect();
+		let state = Arc::new(Mutex::new(PlayState { samples, pos: 0 }));
+		let out_channels = config.channels().max(1) as usize;
+		let stream_config: cpal::StreamConfig = config.clone().into();
+		let stream = match config.sample_format() {
+			cpal::SampleFormat::F32 => build_stream::<f32>(&device, &stream_config, out_channels, state.clone())?,
+			cpal::SampleFormat::I16 => build_stream::<i16>(&device, &stream_config, out_channels, state.clone())?,
+			cpal::SampleFormat::U16 => build_stream::<u16>(&device, &stream_config, out_channels, state.clone())?,
+			cpal::SampleFormat::I32 => build_stream::<i32>(&device, &stream_config, out_channels, state.clone())?,
+			other => return Err(anyhow!("Unsupported output sample format: {:?}", other)),
+		};
+		stream.play()?;
+		Ok(Self { _stream: stream, state })
+	}
+
+	fn is_playing(&self) -> bool {
+		let s = self.state.lock();
+		s.pos < s.samples.len()
+	}
+}
+
+fn build_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, channels: usize, state: Arc<Mutex<PlayState>>) -> Result<cpal::Stream>
+where
+	T: SizedSample + FromSample<f32>,
+{
+	let stream = device.build_output_stream(
+		config,
+		move |data: &mut [T], _| {
+			let mut s = state.lock();
+			for frame in data.chunks_mut(channels) {
+				let v = s.samples.get(s.pos).copied().unwrap_or(0.0);
+				if s.pos < s.samples.len() { s.pos += 1; }
+				for out in frame.iter_mut() { *out = T::from_sample(v); }
+			}
+		},
+		|err| eprintln!("Audio output error: {}", err),
+		None,
+	)?;
+	Ok(stream)
+}
+
+/// Linear interpolation is plenty for speech going to a speaker.
+fn resample_linear(input: &[f32], from: u32, to: u32) -> Vec<f32> {
+	if from == to || input.is_empty() { return input.to_vec(); }
+	let step = from as f64 / to as f64;
+	let len = (input.len() as f64 / step).floor() as usize;
+	(0..len)
+		.map(|i| {
+			let t = i as f64 * step;
+			let idx = t as usize;
+			let frac = (t - idx as f64) as f32;
+			let a = input[idx];
+			let b = input.get(idx + 1).copied().unwrap_or(a);
+			a + (b - a) * frac
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resample_linear_passes_through_equal_rates_and_empty_input() {
+		assert_eq!(resample_linear(&[0.1, 0.2], 22050, 22050), [0.1, 0.2]);
+		assert!(resample_linear(&[], 22050, 48000).is_empty());
+	}
+
+	#[test]
+	fn resample_linear_interpolates_when_upsampling() {
+		assert_eq!(resample_linear(&[0.0, 1.0, 0.0], 1, 2), [0.0, 0.5, 1.0, 0.5, 0.0, 0.0]);
+	}
+
+	#[test]
+	fn resample_linear_skips_samples_when_downsampling() {
+		assert_eq!(resample_linear(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3, 1), [0.0, 3.0]);
+		assert_eq!(resample_linear(&vec![0.0; 22050], 22050, 48000).len(), 48000);
+	}
+}
\ No newline at end of file
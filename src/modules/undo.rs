@@ -0,0 +1,337 @@
+#![cfg(feature = "actions")]
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::memory;
+use crate::modules::actions;
+use crate::modules::policy::{Access, PathPolicy};
+use crate::settings::Settings;
+
+const DEFAULT_MAX_ENTRIES: usize = 200;
+const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+
+/// What a mutating action did, with enough kept to reverse it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+	/// A write or append; `backup` holds the previous content, None when the action created the file
+	File { backup: Option<PathBuf> },
+	/// mkdir; `top` is the outermost directory it had to create
+	Dir { top: PathBuf },
+	/// Moved into the trash as `stored`
+	Trash { stored: PathBuf },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+	pub id: u64,
+	pub action: String,
+	pub path: PathBuf,
+	pub at: DateTime<Utc>,
+	#[serde(flatten)]
+	pub change: Change,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Journal {
+	next_id: u64,
+	entries: Vec<Entry>,
+}
+
+fn backup_dir() -> Result<PathBuf> {
+	let dir = memory::data_dir()?.join("backups");
+	fs::create_dir_all(&dir)?;
+	Ok(dir)
+}
+
+fn load() -> Result<Journal> {
+	match fs::read_to_string(backup_dir()?.join("index.json")) {
+		Ok(s) => Ok(serde_json::from_str(&s)?),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Journal::default()),
+		Err(e) => Err(e.into()),
+	}
+}
+
+fn save(journal: &Journal) -> Result<()> {
+	fs::write(backup_dir()?.join("index.json"), serde_json::to_string_pretty(journal)?)?;
+	Ok(())
+}
+
+/// Copy `path` into the backup area before it is modified.
+pub fn snapshot_file(path: &Path) -> Result<Change> {
+	if !path.exists() { return Ok(Change::File { backup: None }); }
+	let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "file".to_string());
+	let backup = backup_dir()?.join(format!("{}-{}", Utc::now().format("%Y%m%d-%H%M%S%.6f"), name));
+	fs::copy(path, &backup)?;
+	Ok(Change::File { backup: Some(backup) })
+}
+
+/// Journal a completed change and apply the retention policy.
+pub fn record(settings: &Settings, action: &str, path: &Path, change: Change) -> Result<u64> {
+	let mut journal = load()?;
+	let id = journal.next_id.max(1);
+	journal.next_id = id + 1;
+	let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+	journal.entries.push(Entry { id, action: action.to_string(), path, at: Utc::now(), change });
+	prune(settings, &mut journal)?;
+	save(&journal)?;
+	Ok(id)
+}
+
+/// Keep at most `backup_max_entries` changes, none older than `backup_max_age_days`, and delete
+/// backup files no longer referenced (including ones left by actions that failed). Trashed items
+/// go with their entry, since nothing could restore them afterwards. The newest change is always
+/// kept, so the undo id its action just reported stays valid.
+fn prune(settings: &Settings, journal: &mut Journal) -> Result<()> {
+	let max_entries = settings.backup_max_entries.unwrap_or(DEFAULT_MAX_ENTRIES).max(1);
+	let cutoff = Utc::now() - Duration::days(settings.backup_max_age_days.unwrap_or(DEFAULT_MAX_AGE_DAYS) as i64);
+	let newest = journal.entries.last().map(|e| e.id);
+	journal.entries.retain(|e| e.at >= cutoff || Some(e.id) == newest);
+	let excess = journal.entries.len().saturating_sub(max_entries);
+	journal.entries.drain(..excess);
+	let keep: HashSet<&PathBuf> = journal.entries.iter().filter_map(|e| match &e.change {
+		Change::File { backup: Some(b) } => Some(b),
+		_ => None,
+	}).collect();
+	for entry in fs::read_dir(backup_dir()?)?.filter_map(|e| e.ok()) {
+		let path = entry.path();
+		if path.file_name().is_some_and(|n| n == "index.json") || keep.contains(&path) { continue; }
+		fs::remove_file(&path)?;
+	}
+	let trashed: HashSet<&PathBuf> = journal.entries.iter().filter_map(|e| match &e.change {
+		Change::Trash { stored } => Some(stored),
+		_ => None,
+	}).collect();
+	actions::purge_trash(&trashed)
+}
+
+/// Recorded changes, newest first.
+pub fn history() -> Result<String> {
+	let journal = load()?;
+	if journal.entries.is_empty() { return Ok("Nothing to undo".to_string()); }
+	Ok(journal.entries.iter().rev().map(|e| format!(
+		"{:>4}  {}  {:<6} {}",
+		e.id, e.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"), e.action, e.path.display()
+	)).collect::<Vec<_>>().join("\n"))
+}
+
+/// Revert the newest `count` changes, newest first, stopping at the first that fails.
+pub fn undo_last(settings: &Settings, count: usize) -> Result<Vec<String>> {
+	let mut journal = load()?;
+	let mut done = Vec::new();
+	for _ in 0..count {
+		let Some(entry) = journal.entries.last().cloned() else { break };
+		done.push(revert(settings, &entry)?);
+		journal.entries.pop();
+		save(&journal)?;
+	}
+	if done.is_empty() { return Err(anyhow!("Nothing to undo")); }
+	Ok(done)
+}
+
+/// Revert one change. Refused while newer changes to the same path, anything under it or a
+/// directory above it exist, since restoring it would silently discard them or fail halfway.
+pub fn undo_id(settings: &Settings, id: u64) -> Result<String> {
+	let mut journal = load()?;
+	let pos = journal.entries.iter().position(|e| e.id == id).ok_or_else(|| anyhow!("No change with id {} (see `milyai act history`)", id))?;
+	let entry = journal.entries[pos].clone();
+	let newer: Vec<String> = journal.entries[pos + 1..].iter().filter(|e| e.path.starts_with(&entry.path) || entry.path.starts_with(&e.path)).map(|e| e.id.to_string()).collect();
+	if !newer.is_empty() {
+		return Err(anyhow!("{} was changed again later (ids {}); undo those first", entry.path.display(), newer.join(", ")));
+	}
+	let outcome = revert(settings, &entry)?;
+	journal.entries.remove(pos);
+	save(&journal)?;
+	Ok(outcome)
+}
+
+fn revert(settings: &Settings, entry: &Entry) -> Result<String> {
+	PathPolicy::from_settings(settings)?.check(&entry.path, Access::Write)?;
+	match &entry.change {
+		Change::File { backup: Some(backup) } => {
+			fs::copy(backup, &entry.path)?;
+			fs::remove_file(backup)?;
+			Ok(format!("Restored {} to before {} #{}", entry.path.display(), entry.action, entry.id))
+		}
+		Change::File { backup: None } => {
+			if entry.path.exists() { fs::remove_file(&entry.path)?; }
+			Ok(format!("Removed {} (created by {} #{})", entry.path.display(), entry.action, entry.id))
+		}
+		Change::Dir { top } => {
+			// only directories that are still empty, so nothing added since is lost
+			let mut dir = entry.path.as_path();
+			loop {
+				if dir.exists() {
+					fs::remove_dir(dir).map_err(|e| anyhow!("Cannot remove {}: {}", dir.display(), e))?;
+				}
+				if dir == top { break; }
+				dir = dir.parent().ok_or_else(|| anyhow!("{} is not under {}", entry.path.display(), top.display()))?;
+			}
+			Ok(format!("Removed directory {} (created by {} #{})", top.display(), entry.action, entry.id))
+		}
+		Change::Trash { stored } => {
+			actions::restore_from_trash(stored, &entry.path)?;
+			Ok(format!("Restored {} from the trash", entry.path.display()))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::modules::actions::{execute, trash_entries, Action};
+	use tempfile::TempDir;
+
+	/// An allowed root and a private data dir in a fresh temp dir, with actions running unasked.
+	fn setup() -> (TempDir, PathBuf, Settings) {
+		let tmp = tempfile::tempdir().unwrap();
+		let base = fs::canonicalize(tmp.path()).unwrap();
+		memory::set_test_data_dir(&base.join("data"));
+		let root = base.join("root");
+		fs::create_dir(&root).unwrap();
+		let settings = Settings { allow_dirs: Some(vec![root.clone()]), actions_mode: Some("auto".to_string()), ..Default::default() };
+		(tmp, root, settings)
+	}
+
+	async fn run(settings: &Settings, action: Action) -> String {
+		execute(settings, action).await.unwrap()
+	}
+
+	fn write(path: &Path, content: &str) -> Action {
+		Action::WriteFile { path: path.to_path_buf(), content: content.to_string() }
+	}
+
+	fn ids() -> Vec<u64> {
+		load().unwrap().entries.iter().map(|e| e.id).collect()
+	}
+
+	fn backups() -> Vec<PathBuf> {
+		fs::read_dir(backup_dir().unwrap()).unwrap().map(|e| e.unwrap().path()).filter(|p| !p.ends_with("index.json")).collect()
+	}
+
+	#[tokio::test]
+	async fn write_and_append_are_undone_newest_first() {
+		let (_tmp, root, settings) = setup();
+		let file = root.join("notes.txt");
+		run(&settings, write(&file, "one\n")).await;
+		run(&settings, write(&file, "two\n")).await;
+		let reply = run(&settings, Action::AppendFile { path: file.clone(), content: "three\n".to_string() }).await;
+		assert!(reply.contains(&format!("--id {}", ids()[2])), "{}", reply);
+		assert_eq!(fs::read_to_string(&file).unwrap(), "two\nthree\n");
+		assert_eq!(backups().len(), 2);
+
+		undo_last(&settings, 1).unwrap();
+		assert_eq!(fs::read_to_string(&file).unwrap(), "two\n");
+		assert_eq!(undo_last(&settings, 5).unwrap().len(), 2);
+		assert!(!file.exists());
+		assert!(backups().is_empty());
+		assert!(undo_last(&settings, 1).is_err());
+	}
+
+	#[tokio::test]
+	async fn mkdir_undo_removes_only_empty_directories_it_created() {
+		let (_tmp, root, settings) = setup();
+		run(&settings, Action::CreateDir(root.join("a/b/c"))).await;
+		assert!(root.join("a/b/c").is_dir());
+		undo_last(&settings, 1).unwrap();
+		assert!(!root.join("a").exists());
+		assert!(root.is_dir());
+
+		run(&settings, Action::CreateDir(root.join("x/y"))).await;
+		fs::write(root.join("x/y/keep.txt"), "mine").unwrap();
+		let err = undo_last(&settings, 1).unwrap_err();
+		assert!(err.to_string().contains("Cannot remove"), "{}", err);
+		assert_eq!(fs::read_to_string(root.join("x/y/keep.txt")).unwrap(), "mine");
+		assert_eq!(ids().len(), 1);
+	}
+
+	#[tokio::test]
+	async fn trash_undo_restores_without_overwriting() {
+		let (_tmp, root, settings) = setup();
+		let file = root.join("old.txt");
+		fs::write(&file, "data").unwrap();
+		fs::create_dir_all(root.join("dir/sub")).unwrap();
+		fs::write(root.join("dir/sub/inner.txt"), "inner").unwrap();
+		run(&settings, Action::MoveToTrash(file.clone())).await;
+		run(&settings, Action::MoveToTrash(root.join("dir"))).await;
+		assert!(!file.exists() && !root.join("dir").exists());
+		assert_eq!(trash_entries().unwrap().len(), 2);
+
+		let ids = ids();
+		undo_id(&settings, ids[1]).unwrap();
+		assert_eq!(fs::read_to_string(root.join("dir/sub/inner.txt")).unwrap(), "inner");
+
+		fs::write(&file, "new").unwrap();
+		let err = undo_id(&settings, ids[0]).unwrap_err();
+		assert!(err.to_string().contains("exists again"), "{}", err);
+		assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+
+		fs::remove_file(&file).unwrap();
+		undo_id(&settings, ids[0]).unwrap();
+		assert_eq!(fs::read_to_string(&file).unwrap(), "data");
+		assert!(trash_entries().unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn undo_id_refuses_while_the_path_was_changed_later() {
+		let (_tmp, root, settings) = setup();
+		let file = root.join("f.txt");
+		run(&settings, write(&file, "a")).await;
+		run(&settings, write(&file, "b")).await;
+		let [first, second] = ids()[..] else { panic!("{:?}", ids()) };
+		let err = undo_id(&settings, first).unwrap_err();
+		assert!(err.to_string().contains(&format!("changed again later (ids {})", second)), "{}", err);
+		assert_eq!(fs::read_to_string(&file).unwrap(), "b");
+		undo_id(&settings, second).unwrap();
+		assert_eq!(fs::read_to_string(&file).unwrap(), "a");
+		undo_id(&settings, first).unwrap();
+		assert!(!file.exists());
+
+		// a change inside a directory blocks undoing the directory, and the other way round
+		run(&settings, Action::CreateDir(root.join("p"))).await;
+		run(&settings, write(&root.join("p/inner.txt"), "x")).await;
+		let [mkdir, inner] = ids()[..] else { panic!("{:?}", ids()) };
+		assert!(undo_id(&settings, mkdir).unwrap_err().to_string().contains("changed again later"));
+		assert!(undo_id(&settings, 999).is_err());
+		undo_id(&settings, inner).unwrap();
+		undo_id(&settings, mkdir).unwrap();
+		assert!(!root.join("p").exists());
+	}
+
+	#[tokio::test]
+	async fn latest_change_survives_zero_limits() {
+		let (_tmp, root, settings) = setup();
+		let settings = Settings { backup_max_entries: Some(0), backup_max_age_days: Some(0), ..settings };
+		let file = root.join("f.txt");
+		run(&settings, write(&file, "a")).await;
+		let reply = run(&settings, write(&file, "b")).await;
+		let ids = ids();
+		assert_eq!(ids.len(), 1);
+		assert!(reply.contains(&format!("--id {}", ids[0])), "{}", reply);
+		assert_eq!(backups().len(), 1);
+		undo_id(&settings, ids[0]).unwrap();
+		assert_eq!(fs::read_to_string(&file).unwrap(), "a");
+	}
+
+	#[tokio::test]
+	async fn prune_drops_the_oldest_backups_and_trash() {
+		let (_tmp, root, settings) = setup();
+		let settings = Settings { backup_max_entries: Some(2), ..settings };
+		let file = root.join("f.txt");
+		fs::write(root.join("gone.txt"), "bye").unwrap();
+		run(&settings, Action::MoveToTrash(root.join("gone.txt"))).await;
+		for v in ["v0", "v1", "v2", "v3"] { run(&settings, write(&file, v)).await; }
+		assert_eq!(ids().len(), 2);
+		assert_eq!(backups().len(), 2);
+		// the trashed item went with its expired entry
+		assert!(trash_entries().unwrap().is_empty());
+		assert_eq!(fs::read_dir(memory::data_dir().unwrap().join("trash")).unwrap().count(), 1);
+
+		assert_eq!(undo_last(&settings, 5).unwrap().len(), 2);
+		assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+	}
+}
\ No newline at end of file
@@ -0,0 +1,249 @@
+#![cfg(feature = "stt-vosk")]
+use crate::settings::Settings;
+
+/// Tuning for the energy VAD. Durations are in milliseconds of audio, not wall time.
+#[derive(Debug, Clone)]
+pub struct VadConfig {
+	pub frame_ms: u32,
+	/// Initial stretch used to measure the noise floor
+	pub calibration_ms: u32,
+	/// Speech starts when frame RMS exceeds noise floor × this
+	pub start_ratio: f32,
+	/// Speech continues while frame RMS stays above noise floor × this (hysteresis)
+	pub stop_ratio: f32,
+	/// Absolute RMS below which nothing counts as speech, for near-silent inputs
+	pub min_level: f32,
+	/// Loud frames needed before speech is confirmed, to ignore clicks
+	pub min_speech_ms: u32,
+	/// Trailing quiet that ends an utterance
+	pub silence_ms: u32,
+	/// Audio kept from before speech is confirmed, so the first syllable is not cut
+	pub pre_roll_ms: u32,
+	/// Upper bound on one utterance once speech has started
+	pub max_utterance_ms: u32,
+}
+
+impl Default for VadConfig {
+	fn default() -> Self {
+		Self {
+			frame_ms: 20,
+			calibration_ms: 300,
+			start_ratio: 3.0,
+			stop_ratio: 1.8,
+			min_level: 0.002,
+			min_speech_ms: 120,
+			silence_ms: 900,
+			pre_roll_ms: 300,
+			max_utterance_ms: 20_000,
+		}
+	}
+}
+
+impl VadConfig {
+	pub fn from_settings(settings: &Settings) -> Self {
+		let d = Self::default();
+		Self {
+			silence_ms: settings.vad_silence_ms.unwrap_or(d.silence_ms),
+			// at least one frame, so speech is never cut before it is heard
+			max_utterance_ms: settings.vad_max_utterance_secs.map(|s| s.saturating_mul(1000)).unwrap_or(d.max_utterance_ms).max(d.frame_ms),
+			min_speech_ms: settings.vad_min_speech_ms.unwrap_or(d.min_speech_ms),
+			pre_roll_ms: settings.vad_pre_roll_ms.unwrap_or(d.pre_roll_ms),
+			start_ratio: settings.vad_threshold_ratio.unwrap_or(d.start_ratio),
+			stop_ratio: settings.vad_threshold_ratio.map(|r| (r * 0.6).max(1.1)).unwrap_or(d.stop_ratio),
+			..d
+		}
+	}
+
+	pub fn frame_len(&self, sample_rate: u32) -> usize {
+		(sample_rate as usize * self.frame_ms as usize / 1000).max(1)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadEvent {
+	None,
+	SpeechStart,
+	SpeechEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+	Calibrating,
+	Silence,
+	/// Loud frames seen, not yet long enough to count as speech
+	Pending(u32),
+	/// In speech; counts trailing quiet frames and all frames since the start
+	Speech { quiet: u32, frames: u32 },
+}
+
+/// Frame-by-frame speech detector over mono samples with an adaptive noise floor.
+pub struct Vad {
+	cfg: VadConfig,
+	state: State,
+	calibration: Vec<f32>,
+	noise_floor: f32,
+}
+
+impl Vad {
+	pub fn new(cfg: VadConfig) -> Self {
+		Self { cfg, state: State::Calibrating, calibration: Vec::new(), noise_floor: 0.0 }
+	}
+
+	#[cfg(feature = "speech")]
+	pub fn config(&self) -> &VadConfig { &self.cfg }
+
+	#[cfg(any(test, feature = "speech"))]
+	pub fn noise_floor(&self) -> f32 { self.noise_floor }
+
+	pub fn in_speech(&self) -> bool { matches!(self.state, State::Speech { .. }) }
+
+	/// Drop back to silence, keeping the calibrated noise floor (e.g. after our own playback).
+	#[cfg(feature = "speech")]
+	pub fn reset(&mut self) {
+		if self.state != State::Calibrating { self.state = State::Silence; }
+	}
+
+	/// Feed one frame of `frame_ms` mono audio.
+	pub fn process_frame(&mut self, frame: &[f32]) -> VadEvent {
+		let level = rms(frame);
+		let start = (self.noise_floor * self.cfg.start_ratio).max(self.cfg.min_level);
+		let keep = (self.noise_floor * self.cfg.stop_ratio).max(self.cfg.min_level);
+		let frame_ms = self.cfg.frame_ms.max(1);
+		match self.state {
+			State::Calibrating => {
+				self.calibration.push(level);
+				if self.calibration.len() as u32 * frame_ms >= self.cfg.calibration_ms {
+					// low percentile, so talking during calibration does not inflate the floor much
+					let mut sorted = self.calibration.clone();
+					sorted.sort_by(|a, b| a.total_cmp(b));
+					self.noise_floor = sorted[sorted.len() / 5];
+					self.calibration.clear();
+					self.state = State::Silence;
+				}
+				VadEvent::None
+			}
+			State::Silence => {
+				self.track_noise(level);
+				if level > start { self.state = State::Pending(1); }
+				VadEvent::None
+			}
+			State::Pending(n) => {
+				if level <= keep {
+					self.state = State::Silence;
+					return VadEvent::None;
+				}
+				if (n + 1) * frame_ms >= self.cfg.min_speech_ms {
+					self.state = State::Speech { quiet: 0, frames: 1 };
+					return VadEvent::SpeechStart;
+				}
+				self.state = State::Pending(n + 1);
+				VadEvent::None
+			}
+			State::Speech { quiet, frames } => {
+				let quiet = if level > keep { 0 } else { quiet + 1 };
+				let frames = frames + 1;
+				if quiet * frame_ms >= self.cfg.silence_ms || frames * frame_ms >= self.cfg.max_utterance_ms {
+					self.state = State::Silence;
+					return VadEvent::SpeechEnd;
+				}
+				self.state = State::Speech { quiet, frames };
+				VadEvent::None
+			}
+		}
+	}
+
+	/// Follow slow changes in background noise; falls quickly, rises slowly.
+	fn track_noise(&mut self, level: f32) {
+		let alpha = if level < self.noise_floor { 0.2 } else { 0.01 };
+		self.noise_floor += (level - self.noise_floor) * alpha;
+	}
+}
+
+pub fn rms(frame: &[f32]) -> f32 {
+	if frame.is_empty() { return 0.0; }
+	(frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const RATE: u32 = 16_000;
+
+	/// Deterministic low-level noise, so the floor is above `min_level` and ratios matter.
+	fn noise(frames: usize, seed: &mut u32) -> Vec<Vec<f32>> {
+		let len = VadConfig::default().frame_len(RATE);
+		(0..frames).map(|_| (0..len).map(|_| {
+			*seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+			(*seed >> 8) as f32 / (1u32 << 24) as f32 * 0.02 - 0.01
+		}).collect()).collect()
+	}
+
+	fn tone(frames: usize) -> Vec<Vec<f32>> {
+		let len = VadConfig::default().frame_len(RATE);
+		(0..frames).map(|f| (0..len).map(|i| {
+			let t = (f * len + i) as f32 / RATE as f32;
+			0.3 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+		}).collect()).collect()
+	}
+
+	/// Index of each non-None event.
+	fn events(vad: &mut Vad, frames: &[Vec<f32>]) -> Vec<(usize, VadEvent)> {
+		frames.iter().enumerate().map(|(i, f)| (i, vad.process_frame(f))).filter(|(_, e)| *e != VadEvent::None).collect()
+	}
+
+	fn calibrated(cfg: VadConfig, seed: &mut u32) -> Vad {
+		let frames = (cfg.calibration_ms / cfg.frame_ms) as usize;
+		let mut vad = Vad::new(cfg);
+		assert!(events(&mut vad, &noise(frames, seed)).is_empty());
+		vad
+	}
+
+	#[test]
+	fn silence_is_not_speech() {
+		let mut seed = 1;
+		let mut vad = calibrated(VadConfig::default(), &mut seed);
+		assert!(events(&mut vad, &noise(500, &mut seed)).is_empty());
+		assert!(!vad.in_speech());
+		// uniform noise in ±0.01 has an RMS near 0.0058
+		assert!((0.004..0.008).contains(&vad.noise_floor()), "{}", vad.noise_floor());
+	}
+
+	#[test]
+	fn tone_burst_starts_and_ends_speech() {
+		let mut seed = 2;
+		let cfg = VadConfig::default();
+		let (min_frames, silence_frames) = ((cfg.min_speech_ms / cfg.frame_ms) as usize, (cfg.silence_ms / cfg.frame_ms) as usize);
+		let mut vad = calibrated(cfg, &mut seed);
+		let mut frames = tone(25);
+		frames.extend(noise(100, &mut seed));
+		assert_eq!(events(&mut vad, &frames), vec![(min_frames - 1, VadEvent::SpeechStart), (25 + silence_frames - 1, VadEvent::SpeechEnd)]);
+	}
+
+	#[test]
+	fn click_is_ignored() {
+		let mut seed = 3;
+		let mut vad = calibrated(VadConfig::default(), &mut seed);
+		let mut frames = tone(2);
+		frames.extend(noise(50, &mut seed));
+		assert!(events(&mut vad, &frames).is_empty());
+	}
+
+	#[test]
+	fn long_speech_is_cut_at_max_utterance() {
+		let mut seed = 4;
+		let cfg = VadConfig { max_utterance_ms: 1000, ..Default::default() };
+		let min_frames = (cfg.min_speech_ms / cfg.frame_ms) as usize;
+		let mut vad = calibrated(cfg, &mut seed);
+		let got = events(&mut vad, &tone(200));
+		// 50 frames of speech counted from the start event, then it has to be confirmed again
+		assert_eq!(&got[..2], &[(min_frames - 1, VadEvent::SpeechStart), (min_frames - 1 + 49, VadEvent::SpeechEnd)]);
+		assert_eq!(got[2], (min_frames - 1 + 49 + min_frames, VadEvent::SpeechStart));
+	}
+
+	#[test]
+	fn max_utterance_is_at_least_one_frame() {
+		let settings = Settings { vad_max_utterance_secs: Some(0), ..Default::default() };
+		assert_eq!(VadConfig::from_settings(&settings).max_utterance_ms, VadConfig::default().frame_ms);
+	}
+}
\ No newline at end of file
@@ -0,0 +1,83 @@
+#![cfg(feature = "vision")]
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::settings::Settings;
+
+/// Longest side sent to the model; larger images only cost upload and inference time
+const MAX_SIDE: u32 = 1024;
+
+/// An image prepared for a vision model, with where it came from for the memory note.
+pub struct Picture {
+	pub jpeg: Vec<u8>,
+	pub source: String,
+}
+
+impl Picture {
+	pub fn from_file(path: &Path) -> Result<Self> {
+		let img = image::open(path).map_err(|e| anyhow!("Cannot read image {}: {}", path.display(), e))?;
+		Ok(Self { jpeg: encode_jpeg(&img)?, source: path.display().to_string() })
+	}
+
+	/// Grab a frame from the configured camera.
+	#[cfg(feature = "camera")]
+	pub fn from_camera(settings: &Settings) -> Result<Self> {
+		let mut cam = crate::modules::camera::open(settings)?;
+		let frame = crate::modules::camera::capture(&mut cam)?;
+		let source = format!("camera {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+		Ok(Self { jpeg: encode_jpeg(&DynamicImage::ImageRgb8(frame))?, source })
+	}
+}
+
+/// The image at `path`, or a camera frame when no path is given.
+pub fn picture(settings: &Settings, path: Option<&Path>) -> Result<Picture> {
+	match path {
+		Some(p) => Picture::from_file(p),
+		#[cfg(feature = "camera")]
+		None => Picture::from_camera(settings),
+		#[cfg(not(feature = "camera"))]
+		None => {
+			let _ = settings;
+			Err(anyhow!("No image given and camera support is not built in (--features camera)"))
+		}
+	}
+}
+
+/// Downscale to `MAX_SIDE` and encode as JPEG, which every vision backend accepts.
+pub fn encode_jpeg(img: &DynamicImage) -> Result<Vec<u8>> {
+	let img = if img.width().max(img.height()) > MAX_SIDE { img.thumbnail(MAX_SIDE, MAX_SIDE) } else { img.clone() };
+	let mut out = Cursor::new(Vec::new());
+	DynamicImage::ImageRgb8(img.to_rgb8()).write_to(&mut out, image::ImageFormat::Jpeg)?;
+	Ok(out.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn decode(jpeg: &[u8]) -> DynamicImage {
+		assert_eq!(image::guess_format(jpeg).unwrap(), image::ImageFormat::Jpeg);
+		image::load_from_memory(jpeg).unwrap()
+	}
+
+	#[test]
+	fn encode_jpeg_downscales_to_max_side() {
+		let wide = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(MAX_SIDE * 2, MAX_SIDE, image::Rgba([200, 10, 10, 128])));
+		let out = decode(&encode_jpeg(&wide).unwrap());
+		assert_eq!((out.width(), out.height()), (MAX_SIDE, MAX_SIDE / 2));
+		let tall = DynamicImage::ImageLuma8(image::GrayImage::new(300, MAX_SIDE + 1));
+		assert_eq!(decode(&encode_jpeg(&tall).unwrap()).height(), MAX_SIDE);
+	}
+
+	#[test]
+	fn encode_jpeg_keeps_small_images_as_is() {
+		let small = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(40, 30, image::Rgb([0, 128, 255])));
+		let jpeg = encode_jpeg(&small).unwrap();
+		assert!(jpeg.starts_with(&[0xFF, 0xD8]));
+		let out = decode(&jpeg);
+		assert_eq!((out.width(), out.height()), (40, 30));
+		assert_eq!(out.color(), image::ColorType::Rgb8);
+	}
+}
\ No newline at end of file
@@ -1,13 +1,466 @@
-#![cfg(all(feature = "stt-vosk", feature = "tts"))]
+#![cfg(all(feature = "stt-vosk", feature = "speech"))]
 use anyhow::Result;
-use crate::modules::stt;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use vosk::{CompleteResult, DecodingState, Recognizer};
 
-const WAKE_WORDS: &[&str] = &["milly", "mil ai", "milay", "meli"]; // include common mishears
+use crate::agent::Agent;
+use crate::memory;
+use crate::modules::audio::AudioFormat;
+use crate::modules::stt::{self, Mic, Session, SttModel};
+use crate::modules::tts::SpeechWorker;
+use crate::modules::vad;
+use crate::settings::Settings;
 
-pub fn listen_for_wake_and_query(model_path: Option<&std::path::Path>) -> Result<Option<String>> {
-	let heard = stt::transcribe_for_secs(model_path, 2)?;
-	let heard_lower = heard.to_lowercase();
-	let is_wake = WAKE_WORDS.iter().any(|w| heard_lower.contains(w));
-	if !is_wake { return Ok(None); }
-	Ok(Some(stt::transcribe_for_secs(model_path, 5)?))
-} 
\ No newline at end of file
+/// Used when agent_name is unset or still "Mily"
+const DEFAULT_WAKE_WORDS: &[&str] = &["milly", "mily", "mil ai", "milay", "meli"]; // include common mishears
+const DEFAULT_SENSITIVITY: f32 = 0.5;
+/// Partial results must hold the wake word this many frames at sensitivity 0; none at 1
+const MAX_STABLE_FRAMES: f32 = 15.0;
+/// Longer training transcripts are taken as the user saying something else
+const MAX_VARIANT_WORDS: usize = 3;
+
+/// Audio kept from before the wake word fires, replayed into the full recognizer
+const RING_MS: u32 = 3000;
+/// How long to wait for a query when the wake word was said on its own
+const FOLLOW_UP_MS: u32 = 4000;
+/// Playback at the start of a reply used to measure how loud our own voice is in the mic
+const ECHO_CALIBRATION_MS: u32 = 400;
+/// Barge-in needs speech this far above the measured echo...
+const BARGE_IN_RATIO: f32 = 2.0;
+/// ...for this long
+const BARGE_IN_MS: u32 = 200;
+/// How often the mic is checked while a reply is being generated
+const POLL_MS: u64 = 20;
+/// Share of words found in our own reply above which a transcript is treated as echo
+const ECHO_WORD_RATIO: f32 = 0.7;
+/// Punctuation between the wake word and the query, as in "Mily, ..."
+const WAKE_SEPARATORS: &[char] = &[',', '.', ':', ';', '!', '?', '…', '–', '—'];
+
+/// Keeps the microphone open and the model loaded, spotting wake words with a grammar-restricted
+/// recognizer over a continuous stream instead of fixed recording windows.
+pub struct WakeListener {
+	model: SttModel,
+	mic: Mic,
+	detector: Recognizer,
+	wake_words: Vec<String>,
+	/// Confidence a finalized wake word needs
+	min_conf: f32,
+	/// Consecutive frames a partial result must show the wake word
+	stable_frames: u32,
+	seen_frames: u32,
+	/// Recent mono audio at the model rate
+	ring: VecDeque<i16>,
+}
+
+impl WakeListener {
+	pub fn new(settings: &Settings) -> Result<Self> {
+		let model = stt::load_model(settings)?;
+		let mic = Mic::open(settings, &model)?;
+		let wake_words = wake_words(settings)?;
+		let mut detector = model.grammar_recognizer(&wake_words)?;
+		detector.set_words(true);
+		let sensitivity = settings.wake_sensitivity.unwrap_or(DEFAULT_SENSITIVITY).clamp(0.0, 1.0);
+		Ok(Self {
+			model,
+			mic,
+			detector,
+			wake_words,
+			min_conf: 1.0 - sensitivity,
+			stable_frames: ((1.0 - sensitivity) * MAX_STABLE_FRAMES).round() as u32,
+			seen_frames: 0,
+			ring: VecDeque::new(),
+		})
+	}
+
+	pub fn wake_words(&self) -> &[String] { &self.wake_words }
+
+	/// Block until a wake word is heard and return what was said after it, which may be empty.
+	/// Speech right after the wake word ("Milly, what's the weather") is kept, since the ring
+	/// buffer is replayed into a full recognizer before listening on.
+	pub fn next_query(&mut self) -> Result<String> {
+		let ring_len = (self.model.sample_rate() * RING_MS / 1000) as usize;
+		loop {
+			let (frame, _) = self.mic.next_frame()?;
+			self.ring.extend(&frame);
+			while self.ring.len() > ring_len { self.ring.pop_front(); }
+			if self.detect(&frame) { break; }
+		}
+		self.detector.reset();
+		self.seen_frames = 0;
+		let mut session = self.session()?;
+		let recent: Vec<i16> = self.ring.drain(..).collect();
+		session.accept(&recent);
+		let silence_ms = self.mic.vad().config().silence_ms;
+		let heard = self.mic.capture(session, silence_ms)?;
+		let query = strip_wake(&heard, &self.wake_words);
+		if !query.is_empty() { return Ok(query); }
+		let session = self.session()?;
+		self.mic.capture(session, FOLLOW_UP_MS)
+	}
+
+	/// Listen while `speech` plays `reply`, in case the user talks over it. Returns their new query if
+	/// they did, with a leading wake word removed; otherwise resumes wake-word listening.
+	pub fn listen_during(&mut self, speech: &SpeechWorker, reply: &str) -> Result<Option<String>> {
+		let interrupted = barge_in(speech, &mut self.mic)?;
+		self.follow_up(interrupted, reply)
+	}
+
+	/// Answer `query` out loud, listening for barge-in while the reply is still generated, then
+	/// return the reply and the user's next query if they talked over it.
+	pub async fn respond(&mut self, agent: &mut Agent, query: &str, speech: &SpeechWorker) -> Result<(String, Option<String>)> {
+		let (reply, interrupted) = respond_with_barge_in(agent, query, speech, &mut self.mic).await?;
+		let next = self.follow_up(interrupted, &reply)?;
+		Ok((reply, next))
+	}
+
+	fn follow_up(&mut self, interrupted: Option<Vec<i16>>, reply: &str) -> Result<Option<String>> {
+		let query = match interrupted {
+			Some(initial) => {
+				let heard = self.mic.utterance(&self.model, &initial)?;
+				Some(strip_wake(&heard, &self.wake_words)).filter(|q| !q.is_empty() && !is_echo(q, reply))
+			}
+			None => None,
+		};
+		self.resume();
+		Ok(query)
+	}
+
+	/// Forget audio captured while we were busy (thinking or speaking), so our own voice cannot wake us.
+	pub fn resume(&mut self) {
+		self.mic.discard_buffered();
+		self.ring.clear();
+		self.detector.reset();
+		self.seen_frames = 0;
+	}
+
+	fn session(&self) -> Result<Session> {
+		Session::new(&self.model, AudioFormat { sample_rate: self.model.sample_rate(), channels: 1 })
+	}
+
+	/// Finalized results are accepted on word confidence; partial ones once they have been stable long enough.
+	fn detect(&mut self, frame: &[i16]) -> bool {
+		match self.detector.accept_waveform(frame) {
+			Ok(DecodingState::Finalized) => {
+				self.seen_frames = 0;
+				let heard = match self.detector.result() {
+					// low-confidence words are masked so they cannot complete a wake phrase
+					CompleteResult::Single(r) => r.result.iter().map(|w| if w.conf >= self.min_conf { w.word } else { "[low]" }).collect::<Vec<_>>().join(" "),
+					other => stt::result_text(other),
+				};
+				contains_wake(&heard, &self.wake_words)
+			}
+			Ok(_) => {
+				let partial = self.detector.partial_result().partial.to_string();
+				if contains_wake(&partial, &self.wake_words) { self.seen_frames += 1; } else { self.seen_frames = 0; }
+				self.seen_frames > self.stable_frames
+			}
+			Err(_) => false,
+		}
+	}
+}
+
+/// Stream a reply for `input` into `speech` while watching the mic, so the user can talk over it
+/// from the first spoken sentence on, not only once generation is done. Returns the reply as far as
+/// it got and, when the user interrupted, the audio of what they said; generation stops then too.
+pub async fn respond_with_barge_in(agent: &mut Agent, input: &str, speech: &SpeechWorker, mic: &mut Mic) -> Result<(String, Option<Vec<i16>>)> {
+	let mut monitor = BargeIn::new(mic);
+	let mut reply = String::new();
+	let interrupted = {
+		let mut on_text = |t: &str| {
+			print!("{}", t);
+			let _ = io::stdout().flush();
+			speech.push(t);
+			reply.push_str(t);
+		};
+		let generate = agent.respond_streaming(input, &mut on_text);
+		let watch = async {
+			loop {
+				if let Some(heard) = monitor.poll(speech, mic)? { return Ok::<_, anyhow::Error>(heard); }
+				tokio::time::sleep(Duration::from_millis(POLL_MS)).await;
+			}
+		};
+		tokio::select! {
+			done = generate => { done?; None }
+			heard = watch => Some(heard?),
+		}
+	};
+	println!();
+	let interrupted = match interrupted {
+		Some(heard) => Some(heard),
+		None => {
+			speech.finish();
+			monitor.wait(speech, mic)?
+		}
+	};
+	Ok((reply, interrupted))
+}
+
+/// Watch the mic with the VAD while `speech` is busy. If the user talks over it, speech stops at
+/// once and the audio of what they said so far is returned to start the next turn.
+pub fn barge_in(speech: &SpeechWorker, mic: &mut Mic) -> Result<Option<Vec<i16>>> {
+	BargeIn::new(mic).wait(speech, mic)
+}
+
+/// Barge-in detection over mic frames. Our own voice leaks into the mic, so the level of that echo
+/// is measured during the first part of playback and barge-in needs sustained speech clearly above
+/// it. Recent frames are kept so the returned audio includes the pre-roll before it got loud.
+pub struct BargeIn {
+	calibration_frames: usize,
+	needed: usize,
+	echo_levels: Vec<f32>,
+	echo_level: Option<f32>,
+	recent: VecDeque<Vec<i16>>,
+	keep: usize,
+	loud: usize,
+}
+
+impl BargeIn {
+	pub fn new(mic: &Mic) -> Self {
+		let frame_ms = mic.frame_ms();
+		let calibration_frames = (ECHO_CALIBRATION_MS / frame_ms).max(1) as usize;
+		let needed = (BARGE_IN_MS / frame_ms).max(1) as usize;
+		let keep = needed + (mic.vad().config().pre_roll_ms / frame_ms) as usize;
+		Self {
+			calibration_frames,
+			needed,
+			echo_levels: Vec::with_capacity(calibration_frames),
+			echo_level: None,
+			recent: VecDeque::with_capacity(keep + 1),
+			keep,
+			loud: 0,
+		}
+	}
+
+	/// Handle frames captured so far without waiting for more.
+	pub fn poll(&mut self, speech: &SpeechWorker, mic: &mut Mic) -> Result<Option<Vec<i16>>> {
+		while let Some((frame, _)) = mic.try_next_frame()? {
+			if let Some(heard) = self.frame(mic, frame, speech.is_playing()) {
+				speech.cancel();
+				return Ok(Some(heard));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Listen until `speech` is done or the user barges in. Audio after playback ends is dropped.
+	pub fn wait(&mut self, speech: &SpeechWorker, mic: &mut Mic) -> Result<Option<Vec<i16>>> {
+		while speech.is_busy() {
+			let (frame, _) = mic.next_frame()?;
+			if let Some(heard) = self.frame(mic, frame, speech.is_playing()) {
+				speech.cancel();
+				return Ok(Some(heard));
+			}
+		}
+		mic.discard_buffered();
+		Ok(None)
+	}
+
+	fn frame(&mut self, mic: &Mic, frame: Vec<i16>, playing: bool) -> Option<Vec<i16>> {
+		let level = vad::rms(&stt::to_f32(&frame));
+		self.recent.push_back(frame);
+		if self.recent.len() > self.keep { self.recent.pop_front(); }
+		// nothing to talk over yet
+		if !playing {
+			self.loud = 0;
+			return None;
+		}
+		let Some(echo_level) = self.echo_level else {
+			self.echo_levels.push(level);
+			if self.echo_levels.len() == self.calibration_frames {
+				self.echo_levels.sort_by(|a, b| a.total_cmp(b));
+				self.echo_level = Some(self.echo_levels[self.echo_levels.len() * 9 / 10]);
+			}
+			return None;
+		};
+		let cfg = mic.vad().config();
+		let threshold = (echo_level * BARGE_IN_RATIO).max(mic.vad().noise_floor() * cfg.start_ratio).max(cfg.min_level);
+		if level > threshold {
+			self.loud += 1;
+			if self.loud >= self.needed { return Some(self.recent.drain(..).flatten().collect()); }
+		} else {
+			self.loud = 0;
+			// follow the echo as the reply gets louder or quieter
+			self.echo_level = Some(echo_level + (level - echo_level) * 0.05);
+		}
+		None
+	}
+}
+
+/// Whether a transcript heard during playback is mostly our own reply coming back through the mic.
+pub fn is_echo(heard: &str, spoken: &str) -> bool {
+	let heard = words(heard);
+	if heard.is_empty() { return false; }
+	let spoken = words(spoken);
+	let matched = heard.iter().filter(|w| spoken.contains(w)).count();
+	matched as f32 / heard.len() as f32 >= ECHO_WORD_RATIO
+}
+
+/// Lowercase words of a transcript, punctuation dropped.
+pub fn words(text: &str) -> Vec<String> {
+	word_spans(text).into_iter().map(|(start, end)| text[start..end].to_lowercase()).collect()
+}
+
+/// Byte ranges of the words `words` returns.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+	let mut spans = Vec::new();
+	let mut start = None;
+	for (i, c) in text.char_indices() {
+		match (c.is_alphanumeric() || c == '\'', start) {
+			(true, None) => start = Some(i),
+			(false, Some(s)) => {
+				spans.push((s, i));
+				start = None;
+			}
+			_ => {}
+		}
+	}
+	if let Some(s) = start { spans.push((s, text.len())); }
+	spans
+}
+
+/// Index just past the first wake phrase in `heard`, matching whole words.
+fn wake_end(heard: &[String], wake_words: &[String]) -> Option<usize> {
+	(0..heard.len()).find_map(|i| {
+		wake_words.iter().map(|w| words(w)).filter(|w| !w.is_empty()).find_map(|w| {
+			(heard.len() >= i + w.len() && heard[i..i + w.len()] == w[..]).then_some(i + w.len())
+		})
+	})
+}
+
+pub fn contains_wake(heard: &str, wake_words: &[String]) -> bool {
+	wake_end(&words(heard), wake_words).is_some()
+}
+
+/// Text following the wake phrase as it was heard, minus the punctuation setting it off; the whole
+/// transcript when the full recognizer heard the wake word differently.
+pub fn strip_wake(heard: &str, wake_words: &[String]) -> String {
+	let spans = word_spans(heard);
+	let w: Vec<String> = spans.iter().map(|&(start, end)| heard[start..end].to_lowercase()).collect();
+	match wake_end(&w, wake_words) {
+		Some(end) => heard[spans[end - 1].1..].trim_start_matches(|c: char| c.is_whitespace() || WAKE_SEPARATORS.contains(&c)).trim_end().to_string(),
+		None => heard.trim().to_string(),
+	}
+}
+
+/// Configured wake words (or defaults from agent_name) plus variants learned by `train_wake`.
+pub fn wake_words(settings: &Settings) -> Result<Vec<String>> {
+	let mut words: Vec<String> = match &settings.wake_words {
+		Some(w) => w.clone(),
+		None => default_wake_words(settings.agent_name.as_deref()),
+	};
+	words.extend(load_variants()?);
+	let mut out: Vec<String> = Vec::new();
+	for w in words.iter().map(|w| w.trim().to_lowercase()).filter(|w| !w.is_empty()) {
+		if !out.contains(&w) { out.push(w); }
+	}
+	Ok(out)
+}
+
+fn default_wake_words(agent_name: Option<&str>) -> Vec<String> {
+	match agent_name.map(|n| n.trim().to_lowercase()) {
+		Some(name) if !name.is_empty() && !DEFAULT_WAKE_WORDS.contains(&name.as_str()) => vec![name],
+		_ => DEFAULT_WAKE_WORDS.iter().map(|w| w.to_string()).collect(),
+	}
+}
+
+fn variants_path() -> Result<PathBuf> {
+	Ok(memory::data_dir()?.join("wake_variants.json"))
+}
+
+fn load_variants() -> Result<Vec<String>> {
+	match fs::read_to_string(variants_path()?) {
+		Ok(s) => Ok(serde_json::from_str(&s)?),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+		Err(e) => Err(e.into()),
+	}
+}
+
+/// Record `count` utterances of the wake word and keep how the full recognizer actually heard them,
+/// so mishears like "melly" wake us too. Returns the newly added variants.
+pub fn train_wake(settings: &Settings, count: usize) -> Result<Vec<String>> {
+	let model = stt::load_model(settings)?;
+	let mut mic = Mic::open(settings, &model)?;
+	let known = wake_words(settings)?;
+	let mut variants = load_variants()?;
+	let mut added = Vec::new();
+	for i in 1..=count {
+		println!("({}/{}) Say the wake word, e.g. \"{}\"...", i, count, known.first().map(String::as_str).unwrap_or("milly"));
+		mic.discard_buffered();
+		let heard = mic.utterance(&model, &[])?.trim().to_lowercase();
+		if heard.is_empty() || heard.split_whitespace().count() > MAX_VARIANT_WORDS {
+			println!("  heard \"{}\", skipped", heard);
+			continue;
+		}
+		if known.contains(&heard) || variants.contains(&heard) {
+			println!("  heard \"{}\", already known", heard);
+			continue;
+		}
+		println!("  heard \"{}\", added", heard);
+		variants.push(heard.clone());
+		added.push(heard);
+	}
+	fs::write(variants_path()?, serde_json::to_string_pretty(&variants)?)?;
+	Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn wake(list: &[&str]) -> Vec<String> {
+		list.iter().map(|w| w.to_string()).collect()
+	}
+
+	#[test]
+	fn words_split_at_punctuation() {
+		assert_eq!(words("Hello, world! It's 3.5"), ["hello", "world", "it's", "3", "5"]);
+		assert_eq!(words("  ...  "), Vec::<String>::new());
+		assert_eq!(words("Selamat pagi, Dunia"), ["selamat", "pagi", "dunia"]);
+	}
+
+	#[test]
+	fn strip_wake_keeps_the_query_as_heard() {
+		let list = wake(&["mily", "mil ai"]);
+		assert_eq!(strip_wake("mily, berapa 3.5 + 2?", &list), "berapa 3.5 + 2?");
+		assert_eq!(strip_wake("Hey Mily: What's up", &list), "What's up");
+		assert_eq!(strip_wake("mil-ai — nyalakan lampu", &list), "nyalakan lampu");
+		assert_eq!(strip_wake("mily", &list), "");
+		assert_eq!(strip_wake("mily mily apa kabar", &list), "mily apa kabar");
+		// no wake phrase: the transcript as a whole
+		assert_eq!(strip_wake("  family dinner  ", &list), "family dinner");
+	}
+
+	#[test]
+	fn contains_wake_matches_whole_words() {
+		let list = wake(&["milly", "mil ai"]);
+		assert!(contains_wake("ok milly", &list));
+		assert!(contains_wake("MIL AI tolong", &list));
+		assert!(contains_wake("mil-ai", &list));
+		assert!(!contains_wake("family time", &list));
+		assert!(!contains_wake("mil", &list));
+		assert!(!contains_wake("", &list));
+	}
+
+	#[test]
+	fn is_echo_needs_most_words_from_the_reply() {
+		let reply = "The weather in Bandung is sunny today.";
+		assert!(is_echo("the weather is sunny", reply));
+		assert!(is_echo("bandung sunny today", reply));
+		assert!(!is_echo("stop talking", reply));
+		assert!(!is_echo("what is the time", reply));
+		assert!(!is_echo("", reply));
+	}
+
+	#[test]
+	fn default_wake_words_follow_agent_name() {
+		let defaults = wake(DEFAULT_WAKE_WORDS);
+		assert_eq!(default_wake_words(None), defaults);
+		assert_eq!(default_wake_words(Some("Mily")), defaults);
+		assert_eq!(default_wake_words(Some("  ")), defaults);
+		assert_eq!(default_wake_words(Some(" Jarvis ")), ["jarvis"]);
+	}
+}
\ No newline at end of file
@@ -1,18 +1,40 @@
-#![cfg(all(feature = "stt-vosk", feature = "tts"))]
+#![cfg(all(feature = "stt-vosk", feature = "speech"))]
 use anyhow::Result;
-use crate::modules::{stt, tts};
+use crate::modules::voicecmd::{self, CommandTable, Outcome};
+use crate::modules::{stt, tts, voice};
 use crate::agent::Agent;
 use crate::settings::Settings;
 
 pub async fn run(settings: Settings) -> Result<()> {
 	let mut agent = Agent::new(settings.clone())?;
-	println!("Voice chat mode. Speak; it will auto-detect silence. Ctrl+C to exit.");
+	let model = stt::load_model(&settings)?;
+	let mut mic = stt::Mic::open(&settings, &model)?;
+	let speech = tts::SpeechWorker::spawn(&settings)?;
+	let commands = CommandTable::from_settings(&settings);
+	println!("Voice chat mode. Speak; it will auto-detect silence. Talk over me to interrupt. Say \"berhenti mendengarkan\" to exit.");
+	let mut initial: Vec<i16> = Vec::new();
+	let mut last_reply = String::new();
 	loop {
-		let user = stt::transcribe_until_silence(settings.stt_model_path.as_deref(), 900, 20)?;
+		let user = mic.utterance(&model, &initial)?;
+		let barged_in = !initial.is_empty();
+		initial.clear();
 		if user.trim().is_empty() { continue; }
+		if barged_in && voice::is_echo(&user, &last_reply) { continue; }
+		if let Some(cmd) = commands.match_command(&user) {
+			println!("You: {} [{:?}]", user, cmd);
+			if let Outcome::Exit = voicecmd::apply(&cmd, &speech, &mut agent, &last_reply) { break; }
+			if let Some(heard) = voice::barge_in(&speech, &mut mic)? { initial = heard; }
+			continue;
+		}
 		println!("You: {}", user);
-		let reply = agent.respond(&user).await?;
-		println!("Mily: {}", reply);
-		let _ = tts::speak(&reply);
+		print!("Mily: ");
+		// speaking starts with the first finished sentence, while the rest is still generated
+		let (reply, interrupted) = voice::respond_with_barge_in(&mut agent, &user, &speech, &mut mic).await?;
+		if let Some(heard) = interrupted {
+			println!("(interrupted)");
+			initial = heard;
+		}
+		last_reply = reply;
 	}
-} 
\ No newline at end of file
+	Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,179 @@
+#![cfg(all(feature = "stt-vosk", feature = "speech"))]
+use crate::agent::Agent;
+use crate::modules::tts::SpeechWorker;
+use crate::modules::voice::words;
+use crate::settings::Settings;
+
+/// Built-in phrases per command key; `voice_commands` in config replaces a key's list.
+const DEFAULT_COMMANDS: &[(&str, &[&str])] = &[
+	("stop", &["stop", "berhenti", "diam", "cukup"]),
+	("repeat", &["ulangi", "repeat", "say that again", "apa tadi"]),
+	("slower", &["lebih pelan", "pelan pelan", "slower", "slow down"]),
+	("faster", &["lebih cepat", "faster", "speed up"]),
+	("louder", &["lebih keras", "lebih kencang", "louder", "speak up"]),
+	("quieter", &["kecilkan suara", "quieter"]),
+	("exit", &["berhenti mendengarkan", "stop listening"]),
+	("language:en", &["bahasa inggris", "speak english", "in english"]),
+	("language:id", &["bahasa indonesia", "speak indonesian", "in indonesian"]),
+];
+
+/// Words allowed around a phrase ("tolong ulangi"); longer utterances go to the LLM
+const MAX_EXTRA_WORDS: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VoiceCommand {
+	Stop,
+	Repeat,
+	Slower,
+	Faster,
+	Louder,
+	Quieter,
+	Exit,
+	/// Reply and speak in this language code, e.g. "en"
+	Language(String),
+}
+
+impl VoiceCommand {
+	/// Keys: stop, repeat, slower, faster, louder, quieter, exit, language:<code>
+	pub fn from_key(key: &str) -> Option<Self> {
+		Some(match key.trim().to_lowercase().as_str() {
+			"stop" => Self::Stop,
+			"repeat" => Self::Repeat,
+			"slower" => Self::Slower,
+			"faster" => Self::Faster,
+			"louder" => Self::Louder,
+			"quieter" => Self::Quieter,
+			"exit" => Self::Exit,
+			other => Self::Language(other.strip_prefix("language:")?.trim().to_string()),
+		})
+	}
+}
+
+pub enum Outcome {
+	Handled,
+	Exit,
+}
+
+/// Command phrases checked against each utterance before it reaches the LLM.
+pub struct CommandTable {
+	entries: Vec<(Vec<String>, VoiceCommand)>,
+}
+
+impl CommandTable {
+	pub fn from_settings(settings: &Settings) -> Self {
+		let mut table: Vec<(String, Vec<String>)> = DEFAULT_COMMANDS.iter().map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect())).collect();
+		for (key, phrases) in settings.voice_commands.iter().flatten() {
+			match table.iter_mut().find(|(k, _)| k == key) {
+				Some((_, v)) => *v = phrases.clone(),
+				None => table.push((key.clone(), phrases.clone())),
+			}
+		}
+		let mut entries = Vec::new();
+		for (key, phrases) in table {
+			let Some(cmd) = VoiceCommand::from_key(&key) else {
+				tracing::warn!("unknown voice command key: {}", key);
+				continue;
+			};
+			entries.extend(phrases.iter().map(|p| words(p)).filter(|w| !w.is_empty()).map(|w| (w, cmd.clone())));
+		}
+		Self { entries }
+	}
+
+	/// The command whose phrase occurs in `utterance`, preferring the longest phrase, if the
+	/// utterance is not much longer than the phrase itself.
+	pub fn match_command(&self, utterance: &str) -> Option<VoiceCommand> {
+		let heard = words(utterance);
+		self.entries
+			.iter()
+			.filter(|(phrase, _)| heard.len() <= phrase.len() + MAX_EXTRA_WORDS && heard.windows(phrase.len()).any(|w| w == &phrase[..]))
+			.max_by_key(|(phrase, _)| phrase.len())
+			.map(|(_, cmd)| cmd.clone())
+	}
+}
+
+pub fn language_name(code: &str) -> String {
+	match code {
+		"en" => "English",
+		"id" => "Bahasa Indonesia",
+		"ms" => "Bahasa Melayu",
+		"jv" => "Basa Jawa",
+		"su" => "Basa Sunda",
+		other => other,
+	}.to_string()
+}
+
+/// Carry out a command for the rest of the session.
+pub fn apply(cmd: &VoiceCommand, speech: &SpeechWorker, agent: &mut Agent, last_reply: &str) -> Outcome {
+	match cmd {
+		VoiceCommand::Stop => speech.cancel(),
+		VoiceCommand::Repeat => {
+			if !last_reply.is_empty() { speech.say(last_reply); }
+		}
+		VoiceCommand::Slower => { speech.adjust(-1, 0); speech.say("Oke."); }
+		VoiceCommand::Faster => { speech.adjust(1, 0); speech.say("Oke."); }
+		VoiceCommand::Louder => { speech.adjust(0, 1); speech.say("Oke."); }
+		VoiceCommand::Quieter => { speech.adjust(0, -1); speech.say("Oke."); }
+		VoiceCommand::Language(code) => {
+			agent.set_reply_language(Some(language_name(code)));
+			speech.set_language(code);
+			speech.say("Oke.");
+		}
+		VoiceCommand::Exit => {
+			speech.cancel();
+			return Outcome::Exit;
+		}
+	}
+	Outcome::Handled
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn defaults() -> CommandTable {
+		CommandTable::from_settings(&Settings::default())
+	}
+
+	#[test]
+	fn longest_phrase_wins() {
+		let table = defaults();
+		assert_eq!(table.match_command("berhenti mendengarkan"), Some(VoiceCommand::Exit));
+		assert_eq!(table.match_command("Stop listening!"), Some(VoiceCommand::Exit));
+		assert_eq!(table.match_command("berhenti"), Some(VoiceCommand::Stop));
+		assert_eq!(table.match_command("STOP."), Some(VoiceCommand::Stop));
+		assert_eq!(table.match_command("lebih pelan dong"), Some(VoiceCommand::Slower));
+		assert_eq!(table.match_command("tolong bahasa inggris"), Some(VoiceCommand::Language("en".to_string())));
+	}
+
+	#[test]
+	fn questions_around_a_phrase_are_not_commands() {
+		let table = defaults();
+		assert_eq!(table.match_command("tolong ulangi sekarang"), Some(VoiceCommand::Repeat));
+		// more than MAX_EXTRA_WORDS around the phrase: a normal question for the LLM
+		assert_eq!(table.match_command("tolong ulangi yang tadi kamu bilang"), None);
+		assert_eq!(table.match_command("how do I stop a rust program"), None);
+		assert_eq!(table.match_command("stopwatch"), None);
+		assert_eq!(table.match_command(""), None);
+	}
+
+	#[test]
+	fn voice_commands_replace_defaults_per_key() {
+		let overrides = [
+			("stop", vec!["halt"]),
+			("language:ja", vec!["bahasa jepang"]),
+			("bogus", vec!["anything"]),
+		];
+		let settings = Settings {
+			voice_commands: Some(overrides.iter().map(|(k, v)| (k.to_string(), v.iter().map(|p| p.to_string()).collect())).collect()),
+			..Default::default()
+		};
+		let table = CommandTable::from_settings(&settings);
+		assert_eq!(table.match_command("halt"), Some(VoiceCommand::Stop));
+		assert_eq!(table.match_command("berhenti"), None);
+		assert_eq!(table.match_command("bahasa jepang"), Some(VoiceCommand::Language("ja".to_string())));
+		assert_eq!(table.match_command("anything"), None);
+		// other keys keep their defaults
+		assert_eq!(table.match_command("ulangi"), Some(VoiceCommand::Repeat));
+		assert_eq!(VoiceCommand::from_key(" Language:EN "), Some(VoiceCommand::Language("en".to_string())));
+	}
+}
\ No newline at end of file
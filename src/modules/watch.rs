@@ -0,0 +1,327 @@
+#![cfg(feature = "camera")]
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDateTime};
+use image::{imageops, GrayImage, RgbImage};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::memory;
+use crate::settings::Settings;
+
+const DEFAULT_INTERVAL_MS: u64 = 500;
+/// Share of pixels in the region that must change to count as motion
+const DEFAULT_MOTION_THRESHOLD: f32 = 0.02;
+const DEFAULT_COOLDOWN_SECS: u64 = 5;
+const DEFAULT_MAX_FILES: usize = 500;
+/// Frames are compared at this width, which also smooths sensor noise
+const COMPARE_WIDTH: u32 = 160;
+/// Luma change (0-255) for a pixel to count as changed
+const PIXEL_DELTA: u8 = 25;
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp", "webp"];
+/// Snapshots are saved as "<time>-<motion|timelapse>.jpg"
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+const SNAPSHOT_KINDS: &[&str] = &["motion", "timelapse"];
+
+pub struct Frame {
+	pub image: RgbImage,
+	pub at: DateTime<Local>,
+}
+
+/// Where watch mode gets its frames: the camera, or an image sequence for replaying and testing.
+pub trait FrameSource {
+	/// The next frame, or None when the source is exhausted.
+	fn next_frame(&mut self) -> Result<Option<Frame>>;
+}
+
+/// Live camera frames, one per interval.
+pub struct CameraFrames {
+	cam: nokhwa::Camera,
+	interval: Duration,
+	started: bool,
+}
+
+impl CameraFrames {
+	pub fn open(settings: &Settings) -> Result<Self> {
+		let interval = Duration::from_millis(settings.watch_interval_ms.unwrap_or(DEFAULT_INTERVAL_MS));
+		Ok(Self { cam: crate::modules::camera::open(settings)?, interval, started: false })
+	}
+}
+
+impl FrameSource for CameraFrames {
+	fn next_frame(&mut self) -> Result<Option<Frame>> {
+		if self.started { std::thread::sleep(self.interval); }
+		self.started = true;
+		Ok(Some(Frame { image: crate::modules::camera::capture(&mut self.cam)?, at: Local::now() }))
+	}
+}
+
+/// Image files from a directory in name order, stamped as if taken one interval apart, so cooldown
+/// and timelapse behave as they would live without waiting in real time.
+pub struct DirFrames {
+	paths: Vec<PathBuf>,
+	pos: usize,
+	start: DateTime<Local>,
+	interval: ChronoDuration,
+}
+
+impl DirFrames {
+	pub fn open(dir: &Path, settings: &Settings) -> Result<Self> {
+		let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+			.map_err(|e| anyhow!("Cannot read frames from {}: {}", dir.display(), e))?
+			.filter_map(|e| e.ok().map(|e| e.path()))
+			.filter(|p| is_image(p))
+			.collect();
+		if paths.is_empty() { return Err(anyhow!("No images in {}", dir.display())); }
+		paths.sort();
+		let interval = ChronoDuration::milliseconds(settings.watch_interval_ms.unwrap_or(DEFAULT_INTERVAL_MS) as i64);
+		Ok(Self { paths, pos: 0, start: Local::now(), interval })
+	}
+}
+
+impl FrameSource for DirFrames {
+	fn next_frame(&mut self) -> Result<Option<Frame>> {
+		let Some(path) = self.paths.get(self.pos) else { return Ok(None) };
+		let image = image::open(path).map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?.to_rgb8();
+		let at = self.start + self.interval * self.pos as i32;
+		self.pos += 1;
+		Ok(Some(Frame { image, at }))
+	}
+}
+
+/// Part of the frame to watch, as fractions of its width and height.
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+	pub x: f32,
+	pub y: f32,
+	pub w: f32,
+	pub h: f32,
+}
+
+impl Region {
+	/// From `watch_region: [x, y, w, h]`.
+	pub fn from_values(v: &[f32]) -> Result<Self> {
+		match v {
+			[x, y, w, h] if *w > 0.0 && *h > 0.0 => Ok(Self { x: x.clamp(0.0, 1.0), y: y.clamp(0.0, 1.0), w: *w, h: *h }),
+			_ => Err(anyhow!("watch_region must be [x, y, width, height] as fractions of the frame")),
+		}
+	}
+
+	/// Pixel bounds (x0, y0, x1, y1) within a `width` x `height` image.
+	fn bounds(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+		let x0 = (self.x * width as f32) as u32;
+		let y0 = (self.y * height as f32) as u32;
+		let x1 = (((self.x + self.w) * width as f32).ceil() as u32).clamp(x0, width);
+		let y1 = (((self.y + self.h) * height as f32).ceil() as u32).clamp(y0, height);
+		(x0, y0, x1, y1)
+	}
+}
+
+/// Frame-difference motion scoring against the previous frame.
+pub struct MotionDetector {
+	region: Option<Region>,
+	prev: Option<GrayImage>,
+}
+
+impl MotionDetector {
+	pub fn new(region: Option<Region>) -> Self {
+		Self { region, prev: None }
+	}
+
+	/// Share (0-1) of watched pixels that changed since the last frame; 0 for the first frame.
+	pub fn score(&mut self, frame: &RgbImage) -> f32 {
+		let height = (frame.height() as u64 * COMPARE_WIDTH as u64 / frame.width().max(1) as u64).max(1) as u32;
+		let gray = imageops::grayscale(&imageops::resize(frame, COMPARE_WIDTH, height, imageops::FilterType::Triangle));
+		let score = match &self.prev {
+			Some(prev) if prev.dimensions() == gray.dimensions() => changed_share(prev, &gray, self.region),
+			_ => 0.0,
+		};
+		self.prev = Some(gray);
+		score
+	}
+}
+
+fn changed_share(a: &GrayImage, b: &GrayImage, region: Option<Region>) -> f32 {
+	let (x0, y0, x1, y1) = match region {
+		Some(r) => r.bounds(a.width(), a.height()),
+		None => (0, 0, a.width(), a.height()),
+	};
+	let total = (x1 - x0) as usize * (y1 - y0) as usize;
+	if total == 0 { return 0.0; }
+	let changed = (y0..y1)
+		.flat_map(|y| (x0..x1).map(move |x| (x, y)))
+		.filter(|&(x, y)| a.get_pixel(x, y)[0].abs_diff(b.get_pixel(x, y)[0]) > PIXEL_DELTA)
+		.count();
+	changed as f32 / total as f32
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+	when: DateTime<Local>,
+	kind: &'a str,
+	score: f32,
+	file: &'a Path,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	description: Option<&'a str>,
+}
+
+pub fn watch_dir(settings: &Settings) -> Result<PathBuf> {
+	let dir = match &settings.watch_dir {
+		Some(d) => d.clone(),
+		None => memory::data_dir()?.join("watch"),
+	};
+	fs::create_dir_all(&dir)?;
+	Ok(dir)
+}
+
+/// Save a snapshot when motion is seen (at most once per cooldown) and every `watch_timelapse_secs`,
+/// logging each to `events.ndjson` in the watch directory. With `describe`, snapshots are also
+/// described by the vision model and remembered.
+pub async fn run(settings: Settings, source: &mut dyn FrameSource, describe: bool) -> Result<()> {
+	#[cfg(not(feature = "vision"))]
+	if describe { return Err(anyhow!("--describe requires --features vision")); }
+	#[cfg(feature = "vision")]
+	let mut agent = if describe { Some(crate::agent::Agent::new(settings.clone())?) } else { None };
+	let dir = watch_dir(&settings)?;
+	let threshold = settings.watch_motion_threshold.unwrap_or(DEFAULT_MOTION_THRESHOLD);
+	let region = settings.watch_region.as_deref().map(Region::from_values).transpose()?;
+	let cooldown = ChronoDuration::seconds(settings.watch_cooldown_secs.unwrap_or(DEFAULT_COOLDOWN_SECS) as i64);
+	let timelapse = settings.watch_timelapse_secs.filter(|s| *s > 0).map(|s| ChronoDuration::seconds(s as i64));
+	let mut detector = MotionDetector::new(region);
+	let mut last_motion: Option<DateTime<Local>> = None;
+	let mut last_timelapse: Option<DateTime<Local>> = None;
+	println!("Watching; snapshots go to {}", dir.display());
+	while let Some(frame) = source.next_frame()? {
+		let score = detector.score(&frame.image);
+		let motion = score >= threshold && last_motion.is_none_or(|t| frame.at - t >= cooldown);
+		let due = timelapse.is_some_and(|every| last_timelapse.is_none_or(|t| frame.at - t >= every));
+		if !motion && !due { continue; }
+		let kind = if motion { "motion" } else { "timelapse" };
+		if motion { last_motion = Some(frame.at); }
+		if due { last_timelapse = Some(frame.at); }
+		let path = dir.join(format!("{}-{}.jpg", frame.at.format(SNAPSHOT_TIME_FORMAT), kind));
+		frame.image.save(&path)?;
+		#[cfg(feature = "vision")]
+		let description = match agent.as_mut() {
+			Some(agent) if motion => describe_snapshot(agent, &path).await,
+			_ => None,
+		};
+		#[cfg(not(feature = "vision"))]
+		let description: Option<String> = None;
+		println!("{} {} ({:.1}% changed) {}", frame.at.format("%H:%M:%S"), kind, score * 100.0, path.display());
+		if let Some(d) = &description { println!("  {}", d); }
+		log_event(&dir, &Event { when: frame.at, kind, score, file: &path, description: description.as_deref() })?;
+		prune(&dir, settings.watch_max_files.unwrap_or(DEFAULT_MAX_FILES), settings.watch_max_age_days)?;
+	}
+	Ok(())
+}
+
+#[cfg(feature = "vision")]
+async fn describe_snapshot(agent: &mut crate::agent::Agent, path: &Path) -> Option<String> {
+	let described = match crate::modules::vision::Picture::from_file(path) {
+		Ok(picture) => agent.look(&picture, None).await,
+		Err(e) => Err(e),
+	};
+	described.map_err(|e| tracing::warn!("describing {} failed: {}", path.display(), e)).ok()
+}
+
+fn log_event(dir: &Path, event: &Event) -> Result<()> {
+	let mut f = OpenOptions::new().create(true).append(true).open(dir.join("events.ndjson"))?;
+	writeln!(f, "{}", serde_json::to_string(event)?)?;
+	Ok(())
+}
+
+/// Keep at most `max_files` snapshots and none older than `max_age_days`, removing the oldest first.
+/// Only files named like our snapshots are touched, so images the user keeps there are safe.
+pub fn prune(dir: &Path, max_files: usize, max_age_days: Option<u64>) -> Result<()> {
+	let mut snapshots: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)?
+		.filter_map(|e| e.ok())
+		.filter(|e| is_snapshot(&e.path()))
+		.map(|e| (e.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH), e.path()))
+		.collect();
+	snapshots.sort();
+	let cutoff = max_age_days.and_then(|d| SystemTime::now().checked_sub(Duration::from_secs(d * 86_400)));
+	let excess = snapshots.len().saturating_sub(max_files);
+	for (i, (modified, path)) in snapshots.iter().enumerate() {
+		if i < excess || cutoff.is_some_and(|c| *modified < c) {
+			fs::remove_file(path)?;
+		}
+	}
+	Ok(())
+}
+
+fn is_image(path: &Path) -> bool {
+	path.extension().and_then(|e| e.to_str()).is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+fn is_snapshot(path: &Path) -> bool {
+	if path.extension().is_none_or(|e| e != "jpg") { return false; }
+	let Some((time, kind)) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.rsplit_once('-')) else { return false };
+	SNAPSHOT_KINDS.contains(&kind) && NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use image::Rgb;
+
+	fn solid(value: u8) -> RgbImage {
+		RgbImage::from_pixel(64, 48, Rgb([value, value, value]))
+	}
+
+	#[test]
+	fn replays_image_sequence_with_motion() {
+		let dir = tempfile::tempdir().unwrap();
+		// names sort into play order; the text file is not a frame
+		solid(40).save(dir.path().join("003.png")).unwrap();
+		solid(40).save(dir.path().join("001.png")).unwrap();
+		solid(40).save(dir.path().join("002.png")).unwrap();
+		solid(220).save(dir.path().join("004.png")).unwrap();
+		fs::write(dir.path().join("notes.txt"), "not a frame").unwrap();
+		let settings = Settings { watch_interval_ms: Some(250), ..Default::default() };
+		let mut frames = DirFrames::open(dir.path(), &settings).unwrap();
+		let mut detector = MotionDetector::new(None);
+		let mut seen = Vec::new();
+		while let Some(frame) = frames.next_frame().unwrap() {
+			seen.push((frame.at, detector.score(&frame.image)));
+		}
+		assert_eq!(seen.len(), 4);
+		assert_eq!(seen[1].0 - seen[0].0, ChronoDuration::milliseconds(250));
+		assert_eq!(seen[3].0 - seen[0].0, ChronoDuration::milliseconds(750));
+		assert_eq!(seen[..3].iter().map(|(_, s)| *s).collect::<Vec<_>>(), vec![0.0, 0.0, 0.0]);
+		assert!(seen[3].1 > DEFAULT_MOTION_THRESHOLD);
+	}
+
+	#[test]
+	fn empty_sequence_is_an_error() {
+		let dir = tempfile::tempdir().unwrap();
+		assert!(DirFrames::open(dir.path(), &Settings::default()).is_err());
+	}
+
+	#[test]
+	fn prune_only_removes_snapshots() {
+		let dir = tempfile::tempdir().unwrap();
+		let names = ["20260101-080000.000-motion.jpg", "20260101-080001.500-timelapse.jpg", "20260101-080002.250-motion.jpg"];
+		for (i, name) in names.iter().enumerate() {
+			let path = dir.path().join(name);
+			fs::write(&path, "x").unwrap();
+			let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000 + i as u64);
+			fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+		}
+		for keep in ["holiday.jpg", "20260101-080000.000-other.jpg", "20260101-080000.000-motion.png", "events.ndjson"] {
+			fs::write(dir.path().join(keep), "x").unwrap();
+		}
+		prune(dir.path(), 1, None).unwrap();
+		let mut left: Vec<String> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+		left.sort();
+		assert_eq!(left, vec![
+			"20260101-080000.000-motion.png",
+			"20260101-080000.000-other.jpg",
+			"20260101-080002.250-motion.jpg",
+			"events.ndjson",
+			"holiday.jpg",
+		]);
+	}
+}
\ No newline at end of file
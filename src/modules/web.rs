@@ -1,25 +1,30 @@
 #![cfg(feature = "web")]
 use anyhow::{anyhow, Result};
-use scraper::{Html, Selector};
 use url::Url;
 
+use crate::modules::extract;
 use crate::settings::Settings;
 
+const DEFAULT_USER_AGENT: &str = "MilyAI/0.1 (+https://example.com)";
+/// Longest a page, search or feed request may take, so one stuck server cannot stall the caller
+const TIMEOUT_SECS: u64 = 30;
+
+/// HTTP client with the configured user agent and the request timeout.
+pub fn client(settings: &Settings) -> Result<reqwest::Client> {
+	Ok(reqwest::Client::builder()
+		.user_agent(settings.web_user_agent.clone().unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()))
+		.timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+		.build()?)
+}
+
 pub async fn fetch_text(settings: &Settings, url: &str) -> Result<String> {
 	check_domain_policy(settings, url)?;
 	if should_block_by_robots(settings, url).await? { return Err(anyhow!("Blocked by robots.txt")); }
-	let client = reqwest::Client::builder()
-		.user_agent(settings.web_user_agent.clone().unwrap_or_else(|| "MilyAI/0.1 (+https://example.com)".to_string()))
-		.build()?;
-	let resp = client.get(url).send().await?;
+	let resp = client(settings)?.get(url).send().await?;
 	if !resp.status().is_success() { return Err(anyhow!("Fetch failed: {}", resp.status())); }
 	let content_type = resp.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("").to_lowercase();
-	let body = resp.text().await?;
-	if content_type.contains("text/html") || body.contains("<html") {
-		Ok(extract_text_from_html(&body))
-	} else {
-		Ok(body)
-	}
+	let body = resp.bytes().await?;
+	extract::extract(&content_type, url, &body)
 }
 
 fn check_domain_policy(settings: &Settings, url: &str) -> Result<()> {
@@ -45,17 +50,4 @@ async fn should_block_by_robots(settings: &Settings, url: &str) -> Result<bool>
 		}
 	}
 	Ok(false)
-}
-
-fn extract_text_from_html(html: &str) -> String {
-	let doc = Html::parse_document(html);
-	let body_sel = Selector::parse("body").unwrap();
-	let mut text = String::new();
-	if let Some(body) = doc.select(&body_sel).next() {
-		for t in body.text() {
-			let s = t.trim();
-			if !s.is_empty() { text.push_str(s); text.push('\n'); }
-		}
-	}
-	text
-} 
\ No newline at end of file
+}
\ No newline at end of file
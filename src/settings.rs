@@ -13,17 +13,104 @@ pub struct Settings {
 	pub log_path: Option<PathBuf>,
 	#[cfg(feature = "stt-vosk")]
 	pub stt_model_path: Option<PathBuf>,
+	/// Rate the Vosk model expects; device audio is resampled to it (default 16000)
+	#[cfg(feature = "stt-vosk")]
+	pub stt_sample_rate: Option<u32>,
+	/// Input device name (or substring) or index from `milyai audio devices`
+	#[cfg(feature = "stt-vosk")]
+	pub stt_input_device: Option<String>,
+	#[cfg(feature = "stt-vosk")]
+	pub vad_silence_ms: Option<u32>,
+	#[cfg(feature = "stt-vosk")]
+	pub vad_max_utterance_secs: Option<u32>,
+	#[cfg(feature = "stt-vosk")]
+	pub vad_min_speech_ms: Option<u32>,
+	#[cfg(feature = "stt-vosk")]
+	pub vad_pre_roll_ms: Option<u32>,
+	/// Speech must be this many times louder than the calibrated noise floor (default 3.0)
+	#[cfg(feature = "stt-vosk")]
+	pub vad_threshold_ratio: Option<f32>,
+	/// Phrases that wake `milyai voice` (default: agent_name). Variants from `voice train-wake` are added on top.
+	#[cfg(feature = "stt-vosk")]
+	pub wake_words: Option<Vec<String>>,
+	/// 0.0-1.0; higher wakes more readily but accepts more false triggers (default 0.5)
+	#[cfg(feature = "stt-vosk")]
+	pub wake_sensitivity: Option<f32>,
+	/// Voice command phrases by key (stop, repeat, slower, faster, louder, quieter, exit, language:<code>)
+	#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+	pub voice_commands: Option<std::collections::HashMap<String, Vec<String>>>,
+	/// Camera index or name (substring) from `milyai camera list` (default: first camera)
+	#[cfg(feature = "camera")]
+	pub camera_device: Option<String>,
+	/// Preferred capture size such as "1280x720"; the closest supported format is used
+	#[cfg(feature = "camera")]
+	pub camera_resolution: Option<String>,
+	#[cfg(feature = "camera")]
+	pub camera_fps: Option<u32>,
+	/// Frames dropped after opening so auto-exposure can settle (default 5)
+	#[cfg(feature = "camera")]
+	pub camera_warmup_frames: Option<u32>,
+	/// Where `milyai watch` saves snapshots and events.ndjson (default: data dir/watch)
+	#[cfg(feature = "camera")]
+	pub watch_dir: Option<PathBuf>,
+	/// Time between watched frames (default 500)
+	#[cfg(feature = "camera")]
+	pub watch_interval_ms: Option<u64>,
+	/// Share of watched pixels that must change to count as motion (default 0.02)
+	#[cfg(feature = "camera")]
+	pub watch_motion_threshold: Option<f32>,
+	/// Only watch this part of the frame: [x, y, width, height] as fractions
+	#[cfg(feature = "camera")]
+	pub watch_region: Option<Vec<f32>>,
+	/// Minimum time between motion snapshots (default 5)
+	#[cfg(feature = "camera")]
+	pub watch_cooldown_secs: Option<u64>,
+	/// Also save a snapshot this often, motion or not
+	#[cfg(feature = "camera")]
+	pub watch_timelapse_secs: Option<u64>,
+	/// Snapshots kept; the oldest are removed first (default 500)
+	#[cfg(feature = "camera")]
+	pub watch_max_files: Option<usize>,
+	#[cfg(feature = "camera")]
+	pub watch_max_age_days: Option<u64>,
+	/// OpenAI-compatible API base for image description, e.g. "http://127.0.0.1:8080/v1" (default: Ollama)
+	#[cfg(feature = "vision")]
+	pub vision_url: Option<String>,
+	/// Multimodal model (default "llava"; with the OpenAI provider and no vision_url: openai_model, else "gpt-4o-mini")
+	#[cfg(feature = "vision")]
+	pub vision_model: Option<String>,
+	#[cfg(feature = "vision")]
+	pub vision_api_key: Option<String>,
+	/// "tesseract" (default)
+	#[cfg(feature = "ocr")]
+	pub ocr_backend: Option<String>,
+	/// OCR binary (default "tesseract" on PATH)
+	#[cfg(feature = "ocr")]
+	pub ocr_command: Option<PathBuf>,
+	/// Tesseract language codes joined with + (default "ind+eng")
+	#[cfg(feature = "ocr")]
+	pub ocr_languages: Option<String>,
 	#[cfg(feature = "web")]
 	pub web_user_agent: Option<String>,
 	#[cfg(feature = "web")]
 	pub learn_urls: Option<Vec<String>>,
 	#[cfg(feature = "feeds")]
 	pub learn_feeds: Option<Vec<String>>,
+	#[cfg(feature = "feeds")]
+	pub feed_max_items: Option<usize>,
+	#[cfg(feature = "feeds")]
+	pub feed_fetch_full: Option<bool>,
 	#[cfg(feature = "web")]
 	pub learn_interval_secs: Option<u64>,
 	#[cfg(feature = "web")]
-	pub allow_domains: Option<Vec<String>>,
+	pub sources_path: Option<PathBuf>,
+	#[cfg(feature = "web")]
+	pub search_url: Option<String>,
 	#[cfg(feature = "web")]
+	pub search_max_results: Option<usize>,
+	#[cfg(any(feature = "web", feature = "actions"))]
+	pub allow_domains: Option<Vec<String>>,
+	#[cfg(any(feature = "web", feature = "actions"))]
 	pub deny_domains: Option<Vec<String>>,
 	#[cfg(feature = "robots")]
 	pub respect_robots: Option<bool>,
@@ -39,36 +126,81 @@ pub struct Settings {
 	pub llama_model_path: Option<PathBuf>,
 	#[cfg(feature = "llm-llama")]
 	pub llama_n_threads: Option<usize>,
-	#[cfg(feature = "actions")]
+	#[cfg(any(feature = "actions", feature = "ingest"))]
 	pub allow_dirs: Option<Vec<PathBuf>>,
+	/// Directories actions and ingest may read but not modify; takes precedence over `allow_dirs`
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	pub read_only_dirs: Option<Vec<PathBuf>>,
+	/// Paths actions and ingest never touch, e.g. "**/.ssh/**"; replaces the built-in list when set
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	pub deny_globs: Option<Vec<String>>,
+	/// Largest file actions read, write or search (default 1 MiB)
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	pub actions_max_file_bytes: Option<u64>,
+	/// Largest file `milyai ingest` reads (default 20 MiB)
+	#[cfg(feature = "ingest")]
+	pub ingest_max_file_bytes: Option<u64>,
 	#[cfg(feature = "actions")]
 	pub allow_apps: Option<Vec<String>>,
+	/// Per program, argument patterns for `act run` (one glob per argument), e.g. git: ["status", "log *"]
+	#[cfg(feature = "actions")]
+	pub command_arg_patterns: Option<std::collections::HashMap<String, Vec<String>>>,
+	#[cfg(feature = "actions")]
+	pub command_timeout_secs: Option<u64>,
+	/// Methods `act http` may use (default GET and HEAD)
+	#[cfg(feature = "actions")]
+	pub allow_http_methods: Option<Vec<String>>,
+	/// Output kept per stream (default 64 KiB)
+	#[cfg(feature = "actions")]
+	pub command_max_output_bytes: Option<usize>,
 	#[cfg(feature = "actions")]
 	pub actions_auto_confirm: Option<bool>,
+	/// "plan" (describe only), "confirm" (ask first, default) or "auto"; `actions_auto_confirm: true` means auto
+	#[cfg(feature = "actions")]
+	pub actions_mode: Option<String>,
+	/// Changes kept for `act undo` (default 200; the latest is always kept)
+	#[cfg(feature = "actions")]
+	pub backup_max_entries: Option<usize>,
+	/// Days changes stay undoable (default 30)
+	#[cfg(feature = "actions")]
+	pub backup_max_age_days: Option<u64>,
 	// Conversation tuning
 	pub temperature: Option<f32>,
 	pub response_max_sentences: Option<u8>,
 	pub speaking_style: Option<String>,
-	#[cfg(feature = "tts")]
+	/// "native" (OS speech engine, default), "piper" or "espeak" (requires --features tts-process)
+	#[cfg(feature = "speech")]
+	pub tts_backend: Option<String>,
+	/// Piper / espeak-ng binary (default "piper" / "espeak-ng" on PATH)
+	#[cfg(feature = "speech")]
+	pub tts_command: Option<PathBuf>,
+	/// Piper voice model (.onnx)
+	#[cfg(feature = "speech")]
+	pub tts_model_path: Option<PathBuf>,
+	#[cfg(feature = "speech")]
 	pub tts_voice: Option<String>,
-	#[cfg(feature = "tts")]
+	/// Engine-specific for native; a speed factor (1.0 = normal) for piper/espeak
+	#[cfg(feature = "speech")]
 	pub tts_rate: Option<f32>,
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	pub tts_pitch: Option<f32>,
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	pub tts_volume: Option<f32>,
 }
 
+/// App config directory (created on demand), home of config.yaml.
+pub fn config_dir() -> Result<PathBuf> {
+	let proj = ProjectDirs::from("com", "MilyAI", "milyai").expect("dirs");
+	let dir = proj.config_dir().to_path_buf();
+	fs::create_dir_all(&dir)?;
+	Ok(dir)
+}
+
 pub fn load(path: Option<&str>) -> Result<Settings> {
 	let mut s = Settings::default();
 	let file_path = match path {
 		Some(p) => PathBuf::from(p),
-		None => {
-			let proj = ProjectDirs::from("com", "MilyAI", "milyai").expect("dirs");
-			let cfg_dir = proj.config_dir();
-			fs::create_dir_all(cfg_dir)?;
-			cfg_dir.join("config.yaml")
-		}
+		None => config_dir()?.join("config.yaml"),
 	};
 	if file_path.exists() {
 		let text = fs::read_to_string(&file_path)?;
@@ -80,13 +212,75 @@ pub fn load(path: Option<&str>) -> Result<Settings> {
 	if let Ok(v) = env::var("MILYAI_CURIOSITY") { s.curiosity = v.parse().ok(); }
 	if let Ok(v) = env::var("MILYAI_LLM_ENDPOINT") { s.llm_endpoint = Some(v); }
 	if let Ok(v) = env::var("MILYAI_LOG_PATH") { s.log_path = Some(v.into()); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_STT_SAMPLE_RATE") { s.stt_sample_rate = v.parse().ok(); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_STT_INPUT_DEVICE") { s.stt_input_device = Some(v); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_VAD_SILENCE_MS") { s.vad_silence_ms = v.parse().ok(); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_VAD_MAX_UTTERANCE_SECS") { s.vad_max_utterance_secs = v.parse().ok(); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_VAD_MIN_SPEECH_MS") { s.vad_min_speech_ms = v.parse().ok(); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_VAD_PRE_ROLL_MS") { s.vad_pre_roll_ms = v.parse().ok(); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_VAD_THRESHOLD_RATIO") { s.vad_threshold_ratio = v.parse().ok(); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_WAKE_WORDS") { s.wake_words = Some(v.split(',').map(|s| s.trim().to_string()).collect()); }
+	#[cfg(feature = "stt-vosk")]
+	if let Ok(v) = env::var("MILYAI_WAKE_SENSITIVITY") { s.wake_sensitivity = v.parse().ok(); }
 	#[cfg(feature = "web")]
 	if let Ok(v) = env::var("MILYAI_WEB_USER_AGENT") { s.web_user_agent = Some(v); }
 	#[cfg(feature = "web")]
 	if let Ok(v) = env::var("MILYAI_LEARN_INTERVAL_SECS") { s.learn_interval_secs = v.parse().ok(); }
+	#[cfg(feature = "feeds")]
+	if let Ok(v) = env::var("MILYAI_FEED_MAX_ITEMS") { s.feed_max_items = v.parse().ok(); }
+	#[cfg(feature = "feeds")]
+	if let Ok(v) = env::var("MILYAI_FEED_FETCH_FULL") { s.feed_fetch_full = Some(v == "1" || v.to_lowercase() == "true"); }
 	#[cfg(feature = "web")]
-	if let Ok(v) = env::var("MILYAI_ALLOW_DOMAINS") { s.allow_domains = Some(v.split(',').map(|s| s.trim().to_string()).collect()); }
+	if let Ok(v) = env::var("MILYAI_SOURCES_PATH") { s.sources_path = Some(v.into()); }
+	#[cfg(feature = "web")]
+	if let Ok(v) = env::var("MILYAI_SEARCH_URL") { s.search_url = Some(v); }
 	#[cfg(feature = "web")]
+	if let Ok(v) = env::var("MILYAI_SEARCH_MAX_RESULTS") { s.search_max_results = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_CAMERA_DEVICE") { s.camera_device = Some(v); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_CAMERA_RESOLUTION") { s.camera_resolution = Some(v); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_CAMERA_FPS") { s.camera_fps = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_CAMERA_WARMUP_FRAMES") { s.camera_warmup_frames = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_DIR") { s.watch_dir = Some(v.into()); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_INTERVAL_MS") { s.watch_interval_ms = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_MOTION_THRESHOLD") { s.watch_motion_threshold = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_REGION") { s.watch_region = v.split(',').map(|s| s.trim().parse().ok()).collect(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_COOLDOWN_SECS") { s.watch_cooldown_secs = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_TIMELAPSE_SECS") { s.watch_timelapse_secs = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_MAX_FILES") { s.watch_max_files = v.parse().ok(); }
+	#[cfg(feature = "camera")]
+	if let Ok(v) = env::var("MILYAI_WATCH_MAX_AGE_DAYS") { s.watch_max_age_days = v.parse().ok(); }
+	#[cfg(feature = "vision")]
+	if let Ok(v) = env::var("MILYAI_VISION_URL") { s.vision_url = Some(v); }
+	#[cfg(feature = "vision")]
+	if let Ok(v) = env::var("MILYAI_VISION_MODEL") { s.vision_model = Some(v); }
+	#[cfg(feature = "vision")]
+	if let Ok(v) = env::var("MILYAI_VISION_API_KEY") { s.vision_api_key = Some(v); }
+	#[cfg(feature = "ocr")]
+	if let Ok(v) = env::var("MILYAI_OCR_COMMAND") { s.ocr_command = Some(v.into()); }
+	#[cfg(feature = "ocr")]
+	if let Ok(v) = env::var("MILYAI_OCR_LANGUAGES") { s.ocr_languages = Some(v); }
+	#[cfg(any(feature = "web", feature = "actions"))]
+	if let Ok(v) = env::var("MILYAI_ALLOW_DOMAINS") { s.allow_domains = Some(v.split(',').map(|s| s.trim().to_string()).collect()); }
+	#[cfg(any(feature = "web", feature = "actions"))]
 	if let Ok(v) = env::var("MILYAI_DENY_DOMAINS") { s.deny_domains = Some(v.split(',').map(|s| s.trim().to_string()).collect()); }
 	#[cfg(feature = "robots")]
 	if let Ok(v) = env::var("MILYAI_RESPECT_ROBOTS") { s.respect_robots = Some(v == "1" || v.to_lowercase() == "true"); }
@@ -102,22 +296,48 @@ pub fn load(path: Option<&str>) -> Result<Settings> {
 	if let Ok(v) = env::var("MILYAI_LLAMA_MODEL_PATH") { s.llama_model_path = Some(v.into()); }
 	#[cfg(feature = "llm-llama")]
 	if let Ok(v) = env::var("MILYAI_LLAMA_N_THREADS") { s.llama_n_threads = v.parse().ok(); }
-	#[cfg(feature = "actions")]
+	#[cfg(any(feature = "actions", feature = "ingest"))]
 	if let Ok(v) = env::var("MILYAI_ALLOW_DIRS") { s.allow_dirs = Some(v.split(';').map(|s| s.trim().into()).collect()); }
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	if let Ok(v) = env::var("MILYAI_READ_ONLY_DIRS") { s.read_only_dirs = Some(v.split(';').map(|s| s.trim().into()).collect()); }
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	if let Ok(v) = env::var("MILYAI_DENY_GLOBS") { s.deny_globs = Some(v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()); }
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	if let Ok(v) = env::var("MILYAI_ACTIONS_MAX_FILE_BYTES") { s.actions_max_file_bytes = v.parse().ok(); }
+	#[cfg(feature = "ingest")]
+	if let Ok(v) = env::var("MILYAI_INGEST_MAX_FILE_BYTES") { s.ingest_max_file_bytes = v.parse().ok(); }
 	#[cfg(feature = "actions")]
 	if let Ok(v) = env::var("MILYAI_ALLOW_APPS") { s.allow_apps = Some(v.split(',').map(|s| s.trim().to_string()).collect()); }
 	#[cfg(feature = "actions")]
+	if let Ok(v) = env::var("MILYAI_ALLOW_HTTP_METHODS") { s.allow_http_methods = Some(v.split(',').map(|s| s.trim().to_uppercase()).collect()); }
+	#[cfg(feature = "actions")]
+	if let Ok(v) = env::var("MILYAI_COMMAND_TIMEOUT_SECS") { s.command_timeout_secs = v.parse().ok(); }
+	#[cfg(feature = "actions")]
+	if let Ok(v) = env::var("MILYAI_COMMAND_MAX_OUTPUT_BYTES") { s.command_max_output_bytes = v.parse().ok(); }
+	#[cfg(feature = "actions")]
 	if let Ok(v) = env::var("MILYAI_ACTIONS_AUTO_CONFIRM") { s.actions_auto_confirm = Some(v == "1" || v.to_lowercase() == "true"); }
+	#[cfg(feature = "actions")]
+	if let Ok(v) = env::var("MILYAI_ACTIONS_MODE") { s.actions_mode = Some(v.to_lowercase()); }
+	#[cfg(feature = "actions")]
+	if let Ok(v) = env::var("MILYAI_BACKUP_MAX_ENTRIES") { s.backup_max_entries = v.parse().ok(); }
+	#[cfg(feature = "actions")]
+	if let Ok(v) = env::var("MILYAI_BACKUP_MAX_AGE_DAYS") { s.backup_max_age_days = v.parse().ok(); }
 	if let Ok(v) = env::var("MILYAI_TEMPERATURE") { s.temperature = v.parse().ok(); }
 	if let Ok(v) = env::var("MILYAI_RESPONSE_MAX_SENTENCES") { s.response_max_sentences = v.parse().ok(); }
 	if let Ok(v) = env::var("MILYAI_SPEAKING_STYLE") { s.speaking_style = Some(v); }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
+	if let Ok(v) = env::var("MILYAI_TTS_BACKEND") { s.tts_backend = Some(v); }
+	#[cfg(feature = "speech")]
+	if let Ok(v) = env::var("MILYAI_TTS_COMMAND") { s.tts_command = Some(v.into()); }
+	#[cfg(feature = "speech")]
+	if let Ok(v) = env::var("MILYAI_TTS_MODEL_PATH") { s.tts_model_path = Some(v.into()); }
+	#[cfg(feature = "speech")]
 	if let Ok(v) = env::var("MILYAI_TTS_VOICE") { s.tts_voice = Some(v); }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	if let Ok(v) = env::var("MILYAI_TTS_RATE") { s.tts_rate = v.parse().ok(); }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	if let Ok(v) = env::var("MILYAI_TTS_PITCH") { s.tts_pitch = v.parse().ok(); }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	if let Ok(v) = env::var("MILYAI_TTS_VOLUME") { s.tts_volume = v.parse().ok(); }
 	Ok(s)
 }
@@ -131,17 +351,83 @@ fn merge(mut base: Settings, other: Settings) -> Settings {
 	if other.log_path.is_some() { base.log_path = other.log_path; }
 	#[cfg(feature = "stt-vosk")]
 	if other.stt_model_path.is_some() { base.stt_model_path = other.stt_model_path; }
+	#[cfg(feature = "stt-vosk")]
+	if other.stt_sample_rate.is_some() { base.stt_sample_rate = other.stt_sample_rate; }
+	#[cfg(feature = "stt-vosk")]
+	if other.stt_input_device.is_some() { base.stt_input_device = other.stt_input_device; }
+	#[cfg(feature = "stt-vosk")]
+	if other.vad_silence_ms.is_some() { base.vad_silence_ms = other.vad_silence_ms; }
+	#[cfg(feature = "stt-vosk")]
+	if other.vad_max_utterance_secs.is_some() { base.vad_max_utterance_secs = other.vad_max_utterance_secs; }
+	#[cfg(feature = "stt-vosk")]
+	if other.vad_min_speech_ms.is_some() { base.vad_min_speech_ms = other.vad_min_speech_ms; }
+	#[cfg(feature = "stt-vosk")]
+	if other.vad_pre_roll_ms.is_some() { base.vad_pre_roll_ms = other.vad_pre_roll_ms; }
+	#[cfg(feature = "stt-vosk")]
+	if other.vad_threshold_ratio.is_some() { base.vad_threshold_ratio = other.vad_threshold_ratio; }
+	#[cfg(feature = "stt-vosk")]
+	if other.wake_words.is_some() { base.wake_words = other.wake_words; }
+	#[cfg(feature = "stt-vosk")]
+	if other.wake_sensitivity.is_some() { base.wake_sensitivity = other.wake_sensitivity; }
+	#[cfg(all(feature = "stt-vosk", feature = "speech"))]
+	if other.voice_commands.is_some() { base.voice_commands = other.voice_commands; }
+	#[cfg(feature = "camera")]
+	if other.camera_device.is_some() { base.camera_device = other.camera_device; }
+	#[cfg(feature = "camera")]
+	if other.camera_resolution.is_some() { base.camera_resolution = other.camera_resolution; }
+	#[cfg(feature = "camera")]
+	if other.camera_fps.is_some() { base.camera_fps = other.camera_fps; }
+	#[cfg(feature = "camera")]
+	if other.camera_warmup_frames.is_some() { base.camera_warmup_frames = other.camera_warmup_frames; }
+	#[cfg(feature = "camera")]
+	if other.watch_dir.is_some() { base.watch_dir = other.watch_dir; }
+	#[cfg(feature = "camera")]
+	if other.watch_interval_ms.is_some() { base.watch_interval_ms = other.watch_interval_ms; }
+	#[cfg(feature = "camera")]
+	if other.watch_motion_threshold.is_some() { base.watch_motion_threshold = other.watch_motion_threshold; }
+	#[cfg(feature = "camera")]
+	if other.watch_region.is_some() { base.watch_region = other.watch_region; }
+	#[cfg(feature = "camera")]
+	if other.watch_cooldown_secs.is_some() { base.watch_cooldown_secs = other.watch_cooldown_secs; }
+	#[cfg(feature = "camera")]
+	if other.watch_timelapse_secs.is_some() { base.watch_timelapse_secs = other.watch_timelapse_secs; }
+	#[cfg(feature = "camera")]
+	if other.watch_max_files.is_some() { base.watch_max_files = other.watch_max_files; }
+	#[cfg(feature = "camera")]
+	if other.watch_max_age_days.is_some() { base.watch_max_age_days = other.watch_max_age_days; }
+	#[cfg(feature = "vision")]
+	if other.vision_url.is_some() { base.vision_url = other.vision_url; }
+	#[cfg(feature = "vision")]
+	if other.vision_model.is_some() { base.vision_model = other.vision_model; }
+	#[cfg(feature = "vision")]
+	if other.vision_api_key.is_some() { base.vision_api_key = other.vision_api_key; }
+	#[cfg(feature = "ocr")]
+	if other.ocr_backend.is_some() { base.ocr_backend = other.ocr_backend; }
+	#[cfg(feature = "ocr")]
+	if other.ocr_command.is_some() { base.ocr_command = other.ocr_command; }
+	#[cfg(feature = "ocr")]
+	if other.ocr_languages.is_some() { base.ocr_languages = other.ocr_languages; }
 	#[cfg(feature = "web")]
 	if other.web_user_agent.is_some() { base.web_user_agent = other.web_user_agent; }
 	#[cfg(feature = "web")]
 	if other.learn_urls.is_some() { base.learn_urls = other.learn_urls; }
 	#[cfg(feature = "feeds")]
 	if other.learn_feeds.is_some() { base.learn_feeds = other.learn_feeds; }
+	#[cfg(feature = "feeds")]
+	if other.feed_max_items.is_some() { base.feed_max_items = other.feed_max_items; }
+	#[cfg(feature = "feeds")]
+	if other.feed_fetch_full.is_some() { base.feed_fetch_full = other.feed_fetch_full; }
 	#[cfg(feature = "web")]
 	if other.learn_interval_secs.is_some() { base.learn_interval_secs = other.learn_interval_secs; }
 	#[cfg(feature = "web")]
-	if other.allow_domains.is_some() { base.allow_domains = other.allow_domains; }
+	if other.sources_path.is_some() { base.sources_path = other.sources_path; }
 	#[cfg(feature = "web")]
+	if other.search_url.is_some() { base.search_url = other.search_url; }
+	#[cfg(feature = "web")]
+	if other.search_max_results.is_some() { base.search_max_results = other.search_max_results; }
+	#[cfg(any(feature = "web", feature = "actions"))]
+	if other.allow_domains.is_some() { base.allow_domains = other.allow_domains; }
+	#[cfg(any(feature = "web", feature = "actions"))]
 	if other.deny_domains.is_some() { base.deny_domains = other.deny_domains; }
 	#[cfg(feature = "robots")]
 	if other.respect_robots.is_some() { base.respect_robots = other.respect_robots; }
@@ -157,22 +443,50 @@ fn merge(mut base: Settings, other: Settings) -> Settings {
 	if other.llama_model_path.is_some() { base.llama_model_path = other.llama_model_path; }
 	#[cfg(feature = "llm-llama")]
 	if other.llama_n_threads.is_some() { base.llama_n_threads = other.llama_n_threads; }
-	#[cfg(feature = "actions")]
+	#[cfg(any(feature = "actions", feature = "ingest"))]
 	if other.allow_dirs.is_some() { base.allow_dirs = other.allow_dirs; }
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	if other.read_only_dirs.is_some() { base.read_only_dirs = other.read_only_dirs; }
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	if other.deny_globs.is_some() { base.deny_globs = other.deny_globs; }
+	#[cfg(any(feature = "actions", feature = "ingest"))]
+	if other.actions_max_file_bytes.is_some() { base.actions_max_file_bytes = other.actions_max_file_bytes; }
+	#[cfg(feature = "ingest")]
+	if other.ingest_max_file_bytes.is_some() { base.ingest_max_file_bytes = other.ingest_max_file_bytes; }
 	#[cfg(feature = "actions")]
 	if other.allow_apps.is_some() { base.allow_apps = other.allow_apps; }
 	#[cfg(feature = "actions")]
+	if other.command_arg_patterns.is_some() { base.command_arg_patterns = other.command_arg_patterns; }
+	#[cfg(feature = "actions")]
+	if other.allow_http_methods.is_some() { base.allow_http_methods = other.allow_http_methods; }
+	#[cfg(feature = "actions")]
+	if other.command_timeout_secs.is_some() { base.command_timeout_secs = other.command_timeout_secs; }
+	#[cfg(feature = "actions")]
+	if other.command_max_output_bytes.is_some() { base.command_max_output_bytes = other.command_max_output_bytes; }
+	#[cfg(feature = "actions")]
 	if other.actions_auto_confirm.is_some() { base.actions_auto_confirm = other.actions_auto_confirm; }
+	#[cfg(feature = "actions")]
+	if other.actions_mode.is_some() { base.actions_mode = other.actions_mode; }
+	#[cfg(feature = "actions")]
+	if other.backup_max_entries.is_some() { base.backup_max_entries = other.backup_max_entries; }
+	#[cfg(feature = "actions")]
+	if other.backup_max_age_days.is_some() { base.backup_max_age_days = other.backup_max_age_days; }
 	if other.temperature.is_some() { base.temperature = other.temperature; }
 	if other.response_max_sentences.is_some() { base.response_max_sentences = other.response_max_sentences; }
 	if other.speaking_style.is_some() { base.speaking_style = other.speaking_style; }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
+	if other.tts_backend.is_some() { base.tts_backend = other.tts_backend; }
+	#[cfg(feature = "speech")]
+	if other.tts_command.is_some() { base.tts_command = other.tts_command; }
+	#[cfg(feature = "speech")]
+	if other.tts_model_path.is_some() { base.tts_model_path = other.tts_model_path; }
+	#[cfg(feature = "speech")]
 	if other.tts_voice.is_some() { base.tts_voice = other.tts_voice; }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	if other.tts_rate.is_some() { base.tts_rate = other.tts_rate; }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	if other.tts_pitch.is_some() { base.tts_pitch = other.tts_pitch; }
-	#[cfg(feature = "tts")]
+	#[cfg(feature = "speech")]
 	if other.tts_volume.is_some() { base.tts_volume = other.tts_volume; }
 	base
 } 
\ No newline at end of file